@@ -13,7 +13,7 @@ pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn similarity_score(list1: &Vec<u32>, counter: &HashMap<u32, u32>) -> u32 {
+fn similarity_score(list1: &[u32], counter: &HashMap<u32, u32>) -> u32 {
     let mut total_score: u32 = 0;
     for num in list1 {
         total_score += num * counter.get(num).unwrap_or(&0)
@@ -22,7 +22,7 @@ fn similarity_score(list1: &Vec<u32>, counter: &HashMap<u32, u32>) -> u32 {
     total_score
 }
 
-fn make_counter(list: &Vec<u32>) -> HashMap<u32, u32> {
+fn make_counter(list: &[u32]) -> HashMap<u32, u32> {
     let mut counter: HashMap<u32, u32> = HashMap::new();
 
     for num in list {
@@ -33,7 +33,7 @@ fn make_counter(list: &Vec<u32>) -> HashMap<u32, u32> {
     counter
 }
 
-fn calculate_distance(list1: &Vec<u32>, list2: &Vec<u32>) -> u32 {
+fn calculate_distance(list1: &[u32], list2: &[u32]) -> u32 {
     let mut total_distance: u32 = 0;
 
     for (l1, l2) in list1.iter().zip(list2.iter()) {
@@ -102,7 +102,7 @@ impl Config {
             };
 
         Ok(Config {
-            file_path: file_path,
+            file_path,
             delimiter: delimiter.to_string(),
         })
     }
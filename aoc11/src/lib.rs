@@ -29,7 +29,7 @@ fn transform_stone(stone: usize) -> Vec<usize> {
 
     if stone == 0 {
         new_stones.push(1);
-    } else if count_digits(stone) % 2 == 0 {
+    } else if count_digits(stone).is_multiple_of(2) {
         let (num1, num2) = split_integer(stone);
         new_stones.push(num1);
         new_stones.push(num2);
@@ -30,9 +30,9 @@ fn safe_counter(contents: &str) -> Result<(u32, u32), Box<dyn Error>> {
     Ok((nsafe, nsafe_loose))
 }
 
-fn check_row_safety_allow_one(row: &Vec<u32>) -> bool {
+fn check_row_safety_allow_one(row: &[u32]) -> bool {
     for (idx, _) in row.iter().enumerate() {
-        let mut row_copy = row.clone();
+        let mut row_copy = row.to_vec();
         row_copy.remove(idx);
         if check_row_safety(&row_copy) {
             return true;
@@ -42,7 +42,7 @@ fn check_row_safety_allow_one(row: &Vec<u32>) -> bool {
     false
 }
 
-fn check_row_safety(row: &Vec<u32>) -> bool {
+fn check_row_safety(row: &[u32]) -> bool {
     let sorted = row.is_sorted_by(|a, b| (a < b) && ((b - a) <= 3));
     let sorted_reverse = row.is_sorted_by(|a, b| (a > b) && ((a - b) <= 3));
 
@@ -58,22 +58,22 @@ mod tests {
 
     #[test]
     fn checker_ascending() {
-        assert!(check_row_safety(&vec![1, 2, 3]));
+        assert!(check_row_safety(&[1, 2, 3]));
     }
 
     #[test]
     fn checker_descending() {
-        assert!(check_row_safety(&vec![3, 2, 1]));
+        assert!(check_row_safety(&[3, 2, 1]));
     }
 
     #[test]
     fn checker_unsafe_order() {
-        assert!(!check_row_safety(&vec![1, 3, 2]));
+        assert!(!check_row_safety(&[1, 3, 2]));
     }
 
     #[test]
     fn checker_unsafe_value() {
-        assert!(!check_row_safety(&vec![1, 2, 6]));
+        assert!(!check_row_safety(&[1, 2, 6]));
     }
 
     #[test]
@@ -84,6 +84,6 @@ mod tests {
 
     #[test]
     fn checker_allow_one() {
-        assert!(check_row_safety_allow_one(&vec![1, 3, 2, 4, 5]));
+        assert!(check_row_safety_allow_one(&[1, 3, 2, 4, 5]));
     }
 }
@@ -1,4 +1,3 @@
-use aoc2;
 use std::process;
 
 
@@ -1,66 +1,347 @@
 use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::ops::Range;
 
-pub fn run(file_path: &str) -> Result<(), Box<dyn Error>>{
-    let instruction = fs::read_to_string(file_path)?;
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    let instruction = fs::read_to_string(&config.file_path)?;
     let mul = total_mul(&instruction);
     let mul_do = total_mul_do(&instruction);
 
     println!("Total multiplication: {}", mul);
     println!("Total multiplication (with do/don't): {}", mul_do);
 
+    if config.report || config.highlight {
+        let report = scan(&instruction);
+        if config.report {
+            print!("{}", report);
+        }
+        if config.highlight {
+            println!("{}", render_highlighted(&instruction, &report));
+        }
+    }
+
     Ok(())
 }
 
 fn total_mul_do(instruction: &str) -> i32 {
-    let inst_vec: Vec<i32> = instruction
-        .replace("do()", "mul(-1,1)")
-        .replace("don't()", "mul(-1,2)")
-        .split("mul(")
-        .map(|elem| elem.split(")"))
-        .flatten()
-        .map(|elem| {
-            let mut iter = elem.split(",");
-            match (iter.next(), iter.next(), iter.next()) {
-                (Some(a), Some(b), None) => {
-                    a.parse::<i32>().unwrap_or(0) * b.parse::<i32>().unwrap_or(0)
-                },
-                _ => 0
+    scan(instruction)
+        .accepted
+        .iter()
+        .filter(|mul| mul.enabled)
+        .map(Mul::product)
+        .sum()
+}
+
+fn total_mul(instruction: &str) -> i32 {
+    scan(instruction).accepted.iter().map(Mul::product).sum()
+}
+
+/// Walks the corrupted memory once and records every `mul` instruction that
+/// was accepted, every `mul` that was rejected, and the regions switched off
+/// by `don't()`.
+///
+/// Only a `mul` followed by punctuation or a space, such as `mul(` or
+/// `mul[`, counts as an attempted instruction; `mul` inside ordinary words
+/// such as "simulate" is not reported.
+pub fn scan(instruction: &str) -> Report {
+    let bytes = instruction.as_bytes();
+    let mut report = Report::default();
+    let mut enabled = true;
+    let mut disabled_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(b"do()") {
+            if !enabled {
+                report.disabled.push(disabled_start..i);
+                enabled = true;
             }
-        })
-        .collect();
-
-    let mut do_inst = true;
-    let mut total = 0;
-
-    for num in inst_vec {
-        if num == -1 {
-            do_inst = true;
-        } else if num == -2 {
-            do_inst = false;
-        } else if do_inst {
-            total += num;
+            i += 4;
+        } else if rest.starts_with(b"don't()") {
+            if enabled {
+                disabled_start = i;
+                enabled = false;
+            }
+            i += 7;
+        } else if rest.starts_with(b"mul") && rest.get(3).is_some_and(|&next| attempts_mul(next)) {
+            match parse_mul(bytes, i) {
+                Ok((lhs, rhs, end)) => {
+                    report.accepted.push(Mul {
+                        offset: i,
+                        len: end - i,
+                        lhs,
+                        rhs,
+                        enabled,
+                    });
+                    i = end;
+                }
+                Err((reason, end)) => {
+                    let end = char_end(instruction, end);
+                    report.near_misses.push(NearMiss {
+                        offset: i,
+                        text: instruction[i..end].to_string(),
+                        reason,
+                    });
+                    i += 3;
+                }
+            }
+        } else {
+            i += 1;
         }
     }
 
-    total
+    if !enabled {
+        report.disabled.push(disabled_start..bytes.len());
+    }
+
+    report
 }
 
-fn total_mul(instruction: &str) -> i32 {
-    instruction
-        .split("mul(")
-        .map(|elem| elem.split(")"))
-        .flatten()
-        .map(|elem| {
-            let mut iter = elem.split(",");
-            match (iter.next(), iter.next(), iter.next()) {
-                (Some(a), Some(b), None) => {
-                    a.parse::<i32>().unwrap_or(0) * b.parse::<i32>().unwrap_or(0)
-                },
-                _ => 0
+/// Whether the byte after `mul` makes it an attempted instruction.
+fn attempts_mul(next: u8) -> bool {
+    next.is_ascii_punctuation() || next == b' '
+}
+
+/// Parses `mul(X,Y)` starting at `start`. On success returns the operands and
+/// the offset just past the closing parenthesis, otherwise the reason and the
+/// offset of the byte that broke the instruction.
+fn parse_mul(bytes: &[u8], start: usize) -> Result<(i32, i32, usize), (Rejection, usize)> {
+    let mut i = start + 3;
+
+    if bytes.get(i) != Some(&b'(') {
+        return Err((Rejection::MissingOpenParen, i));
+    }
+    i += 1;
+
+    let (lhs, next) = parse_operand(bytes, i, Rejection::MissingFirstOperand)?;
+    i = next;
+
+    if bytes.get(i) != Some(&b',') {
+        return Err((Rejection::MissingComma, i));
+    }
+    i += 1;
+
+    let (rhs, next) = parse_operand(bytes, i, Rejection::MissingSecondOperand)?;
+    i = next;
+
+    if bytes.get(i) != Some(&b')') {
+        return Err((Rejection::MissingCloseParen, i));
+    }
+
+    Ok((lhs, rhs, i + 1))
+}
+
+fn parse_operand(
+    bytes: &[u8],
+    start: usize,
+    missing: Rejection,
+) -> Result<(i32, usize), (Rejection, usize)> {
+    let digits = bytes[start..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+
+    match digits {
+        0 => Err((missing, start)),
+        1..=3 => {
+            let value = bytes[start..start + digits]
+                .iter()
+                .fold(0, |acc, byte| acc * 10 + i32::from(byte - b'0'));
+            Ok((value, start + digits))
+        }
+        _ => Err((Rejection::OperandTooLong, start + 3)),
+    }
+}
+
+/// Extends a byte offset to include the whole character starting there, so
+/// near-miss snippets never split a multi-byte character.
+fn char_end(instruction: &str, offset: usize) -> usize {
+    match instruction[offset..].chars().next() {
+        Some(c) => offset + c.len_utf8(),
+        None => offset,
+    }
+}
+
+/// Renders the original text with accepted instructions in green, ignored
+/// (disabled) instructions in dim green, near misses in red and disabled
+/// regions dimmed.
+pub fn render_highlighted(instruction: &str, report: &Report) -> String {
+    let mut styles = vec![Style::Plain; instruction.len()];
+
+    for range in &report.disabled {
+        styles[range.clone()].fill(Style::Disabled);
+    }
+    for near_miss in &report.near_misses {
+        let end = near_miss.offset + near_miss.text.len();
+        styles[near_miss.offset..end].fill(Style::NearMiss);
+    }
+    for mul in &report.accepted {
+        let style = if mul.enabled {
+            Style::Accepted
+        } else {
+            Style::Ignored
+        };
+        styles[mul.offset..mul.offset + mul.len].fill(style);
+    }
+
+    let mut rendered = String::new();
+    let mut current = Style::Plain;
+
+    for (idx, c) in instruction.char_indices() {
+        if styles[idx] != current {
+            current = styles[idx];
+            rendered.push_str(RESET);
+            rendered.push_str(current.code());
+        }
+        rendered.push(c);
+    }
+    rendered.push_str(RESET);
+
+    rendered
+}
+
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    Disabled,
+    Accepted,
+    Ignored,
+    NearMiss,
+}
+
+impl Style {
+    fn code(&self) -> &'static str {
+        match self {
+            Style::Plain => "",
+            Style::Disabled => "\x1b[2m",
+            Style::Accepted => "\x1b[32m",
+            Style::Ignored => "\x1b[2;32m",
+            Style::NearMiss => "\x1b[31m",
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub accepted: Vec<Mul>,
+    pub near_misses: Vec<NearMiss>,
+    pub disabled: Vec<Range<usize>>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Accepted instructions:")?;
+        for mul in &self.accepted {
+            write!(
+                f,
+                "  {:>6}: mul({},{}) = {}",
+                mul.offset,
+                mul.lhs,
+                mul.rhs,
+                mul.product()
+            )?;
+            if !mul.enabled {
+                write!(f, " (disabled)")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Near misses:")?;
+        for near_miss in &self.near_misses {
+            writeln!(
+                f,
+                "  {:>6}: {:?} ({})",
+                near_miss.offset, near_miss.text, near_miss.reason
+            )?;
+        }
+
+        writeln!(f, "Disabled ranges:")?;
+        for range in &self.disabled {
+            writeln!(f, "  {}..{}", range.start, range.end)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mul {
+    pub offset: usize,
+    /// Length in bytes of the instruction as written, e.g. 8 for `mul(2,4)`.
+    pub len: usize,
+    pub lhs: i32,
+    pub rhs: i32,
+    pub enabled: bool,
+}
+
+impl Mul {
+    pub fn product(&self) -> i32 {
+        self.lhs * self.rhs
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NearMiss {
+    pub offset: usize,
+    pub text: String,
+    pub reason: Rejection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    MissingOpenParen,
+    MissingFirstOperand,
+    MissingComma,
+    MissingSecondOperand,
+    MissingCloseParen,
+    OperandTooLong,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Rejection::MissingOpenParen => "expected '(' after mul",
+            Rejection::MissingFirstOperand => "expected a number after '('",
+            Rejection::MissingComma => "expected ',' after the first number",
+            Rejection::MissingSecondOperand => "expected a number after ','",
+            Rejection::MissingCloseParen => "expected ')' after the second number",
+            Rejection::OperandTooLong => "numbers may have at most 3 digits",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+pub struct Config {
+    pub file_path: String,
+    pub report: bool,
+    pub highlight: bool,
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+        args.next();
+
+        let mut report = false;
+        let mut highlight = false;
+
+        for arg in args {
+            match arg.as_str() {
+                "--report" => report = true,
+                "--highlight" => highlight = true,
+                _ => return Err("Unknown argument. Allowed arguments: ['--report', '--highlight']"),
             }
+        }
+
+        Ok(Config {
+            file_path: "data.txt".to_string(),
+            report,
+            highlight,
         })
-        .sum()
+    }
 }
 
 #[cfg(test)]
@@ -73,9 +354,84 @@ mod tests {
         assert_eq!(total_mul(instruction), 161);
     }
 
+    #[test]
+    fn check_total_mul_operands() {
+        // Only one to three unsigned digits make an operand, so none of the
+        // first three instructions count.
+        let instruction = "mul(1234,5)mul(-3,4)mul(+3,4)mul(2,3)";
+        assert_eq!(total_mul(instruction), 6);
+        assert_eq!(total_mul_do(instruction), 6);
+    }
+
     #[test]
     fn check_total_mul_do() {
         let instruction = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
         assert_eq!(total_mul_do(instruction), 48);
     }
+
+    #[test]
+    fn check_scan() {
+        let instruction = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let report = scan(instruction);
+
+        let offsets: Vec<_> = report.accepted.iter().map(|mul| mul.offset).collect();
+        let enabled: Vec<_> = report.accepted.iter().map(|mul| mul.enabled).collect();
+        assert_eq!(offsets, vec![1, 28, 48, 64]);
+        assert_eq!(enabled, vec![true, false, false, true]);
+
+        assert_eq!(
+            report.near_misses,
+            vec![
+                NearMiss {
+                    offset: 10,
+                    text: "mul[".to_string(),
+                    reason: Rejection::MissingOpenParen,
+                },
+                NearMiss {
+                    offset: 37,
+                    text: "mul(32,64]".to_string(),
+                    reason: Rejection::MissingCloseParen,
+                },
+            ]
+        );
+        assert_eq!(report.disabled, vec![20..59]);
+    }
+
+    #[test]
+    fn check_scan_operand_length() {
+        let report = scan("mul(1234,5)mul(123,45)mul(,1)");
+
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.accepted[0].len, "mul(123,45)".len());
+        let reasons: Vec<_> = report.near_misses.iter().map(|miss| miss.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![Rejection::OperandTooLong, Rejection::MissingFirstOperand]
+        );
+    }
+
+    #[test]
+    fn check_scan_ignores_words() {
+        let report = scan("simulate a mulch mul{1,2} mul <3> mul(3,4)");
+
+        assert_eq!(report.accepted.len(), 1);
+        let misses: Vec<_> = report
+            .near_misses
+            .iter()
+            .map(|miss| (miss.offset, miss.text.as_str()))
+            .collect();
+        assert_eq!(misses, vec![(17, "mul{"), (26, "mul ")]);
+    }
+
+    #[test]
+    fn check_render_highlighted() {
+        let instruction = "mul(2,3)don't()mul(1,1]";
+        let report = scan(instruction);
+        let rendered = render_highlighted(instruction, &report);
+
+        assert_eq!(
+            rendered,
+            "\x1b[0m\x1b[32mmul(2,3)\x1b[0m\x1b[2mdon't()\x1b[0m\x1b[31mmul(1,1]\x1b[0m"
+        );
+    }
 }
@@ -1,9 +1,14 @@
-use aoc3;
+use aoc3::Config;
+use std::env;
 use std::process;
 
 fn main() {
-    let file_path = "data.txt";
-    if let Err(e) = aoc3::run(file_path) {
+    let config = Config::build(env::args()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {err}");
+        process::exit(1);
+    });
+
+    if let Err(e) = aoc3::run(config) {
         eprintln!("Application error: {e}");
         process::exit(1);
     }
@@ -0,0 +1,28 @@
+use aoc4::PuzzleConfig;
+use std::env;
+use std::process;
+
+fn main() {
+    let config = PuzzleConfig::build(env::args()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {err}");
+        eprintln!(
+            "Usage: generate ROWS COLUMNS [--seed SEED] [--overlap forbid|allow|prefer] WORD..."
+        );
+        process::exit(1);
+    });
+
+    let puzzle = aoc4::generate(&config).unwrap_or_else(|err| {
+        eprintln!("Problem generating puzzle: {err}");
+        process::exit(1);
+    });
+
+    println!("{}", puzzle.grid);
+    println!();
+    println!("Answer key (seed {}):", config.seed);
+    for found in &puzzle.answers {
+        println!(
+            "{}: row {}, column {}, {:?}",
+            found.word, found.start.0, found.start.1, found.direction
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Direction, Grid, Position, WordMatch};
+
+/// How hidden words may share cells with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// Words never share a cell.
+    Forbid,
+    /// Words may cross where their letters agree.
+    Allow,
+    /// Placements sharing the most letters with earlier words are chosen.
+    Prefer,
+}
+
+impl Overlap {
+    pub fn parse(overlap: &str) -> Result<Overlap, &'static str> {
+        match overlap.to_lowercase().as_str() {
+            "forbid" => Ok(Overlap::Forbid),
+            "allow" => Ok(Overlap::Allow),
+            "prefer" => Ok(Overlap::Prefer),
+            _ => Err("Unknown overlap. Allowed values: ['forbid', 'allow', 'prefer']"),
+        }
+    }
+}
+
+pub struct PuzzleConfig {
+    pub m: usize,
+    pub n: usize,
+    pub words: Vec<String>,
+    pub overlap: Overlap,
+    pub alphabet: Vec<char>,
+    pub seed: u64,
+}
+
+impl PuzzleConfig {
+    pub fn new(m: usize, n: usize, words: Vec<String>) -> PuzzleConfig {
+        PuzzleConfig {
+            m,
+            n,
+            words,
+            overlap: Overlap::Allow,
+            alphabet: ('A'..='Z').collect(),
+            seed: 0,
+        }
+    }
+
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<PuzzleConfig, &'static str> {
+        args.next();
+
+        let m = match args.next() {
+            Some(arg) => arg
+                .parse()
+                .map_err(|_| "Number of rows must be an integer!")?,
+            None => return Err("Did not get the number of rows!"),
+        };
+        let n = match args.next() {
+            Some(arg) => arg
+                .parse()
+                .map_err(|_| "Number of columns must be an integer!")?,
+            None => return Err("Did not get the number of columns!"),
+        };
+
+        let mut config = PuzzleConfig::new(m, n, Vec::new());
+        config.seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next() {
+                    Some(seed) => {
+                        config.seed = seed.parse().map_err(|_| "Seed must be an integer!")?
+                    }
+                    None => return Err("Did not get a seed!"),
+                },
+                "--overlap" => match args.next() {
+                    Some(overlap) => config.overlap = Overlap::parse(&overlap)?,
+                    None => return Err("Did not get an overlap mode!"),
+                },
+                _ => config.words.push(arg),
+            }
+        }
+
+        if config.words.is_empty() {
+            return Err("Did not get any words to hide!");
+        }
+
+        Ok(config)
+    }
+}
+
+pub struct Puzzle {
+    pub grid: Grid,
+    pub answers: Vec<WordMatch>,
+}
+
+/// Hides every word of the config in a fresh grid and fills the remaining
+/// cells so that the solver finds exactly the hidden words, nothing more.
+pub fn generate(config: &PuzzleConfig) -> Result<Puzzle, Box<dyn Error>> {
+    check_words(config)?;
+
+    let mut rng = Rng::new(config.seed);
+    let mut cells: Vec<Option<char>> = vec![None; config.m * config.n];
+    let mut answers: Vec<WordMatch> = Vec::new();
+    let words: Vec<&str> = config.words.iter().map(String::as_str).collect();
+
+    let mut order: Vec<&str> = words.clone();
+    order.sort_by_key(|word| std::cmp::Reverse(word.chars().count()));
+
+    for word in order {
+        let letters: Vec<char> = word.chars().collect();
+        let mut candidates = placements(config, &cells, &letters);
+        if config.overlap == Overlap::Prefer {
+            let most_shared = candidates.iter().map(|(_, shared)| *shared).max();
+            candidates.retain(|(_, shared)| Some(*shared) == most_shared);
+        }
+        rng.shuffle(&mut candidates);
+
+        let mut placed = false;
+        for (found, _) in candidates {
+            let previous = cells.clone();
+            for (position, &c) in found.cells().iter().zip(&letters) {
+                cells[index(config, position)] = Some(c);
+            }
+            answers.push(found);
+
+            if count_matches(config, &cells, &words) == answers.len() {
+                placed = true;
+                break;
+            }
+            answers.pop();
+            cells = previous;
+        }
+
+        if !placed {
+            return Err(
+                format!("Could not hide '{}' without creating extra matches.", word).into(),
+            );
+        }
+    }
+
+    fill(config, &mut cells, &words, &mut rng)?;
+
+    let grid = Grid {
+        m: config.m,
+        n: config.n,
+        values: cells.into_iter().map(Option::unwrap).collect(),
+    };
+
+    Ok(Puzzle { grid, answers })
+}
+
+/// Rejects word lists the solver could never read back unambiguously.
+fn check_words(config: &PuzzleConfig) -> Result<(), Box<dyn Error>> {
+    if config.m == 0 || config.n == 0 {
+        return Err("The grid must have at least one row and one column.".into());
+    }
+
+    for word in &config.words {
+        let length = word.chars().count();
+        if length == 0 {
+            return Err("Cannot hide an empty word.".into());
+        }
+        if length > config.m.max(config.n) {
+            return Err(format!(
+                "'{}' does not fit in a {}x{} grid.",
+                word, config.m, config.n
+            )
+            .into());
+        }
+        let reversed: String = word.chars().rev().collect();
+        for other in &config.words {
+            if other != word && (other.contains(word.as_str()) || other.contains(&reversed)) {
+                return Err(format!("'{}' would also be found inside '{}'.", word, other).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every placement of `letters` consistent with the cells filled so far,
+/// with the number of cells it shares with earlier words.
+fn placements(
+    config: &PuzzleConfig,
+    cells: &[Option<char>],
+    letters: &[char],
+) -> Vec<(WordMatch, usize)> {
+    let mut candidates = Vec::new();
+    let word: String = letters.iter().collect();
+
+    for idx in 0..cells.len() {
+        let start = Position(
+            (idx / config.n).try_into().unwrap(),
+            (idx % config.n).try_into().unwrap(),
+        );
+        for direction in Direction::ALL {
+            let found = WordMatch {
+                start,
+                direction,
+                word: word.clone(),
+            };
+            let positions = found.cells();
+            if !positions.iter().all(|position| in_bounds(config, position)) {
+                continue;
+            }
+
+            let mut shared = 0;
+            let consistent = positions.iter().zip(letters).all(|(position, c)| {
+                match cells[index(config, position)] {
+                    Some(existing) if existing == *c => {
+                        shared += 1;
+                        true
+                    }
+                    Some(_) => false,
+                    None => true,
+                }
+            });
+
+            if consistent && (config.overlap != Overlap::Forbid || shared == 0) {
+                candidates.push((found, shared));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Number of occurrences the solver finds using only the filled cells.
+fn count_matches(config: &PuzzleConfig, cells: &[Option<char>], words: &[&str]) -> usize {
+    let grid = Grid {
+        m: config.m,
+        n: config.n,
+        values: cells.iter().map(|cell| cell.unwrap_or('\0')).collect(),
+    };
+    grid.find_words(words).len()
+}
+
+/// Fills the empty cells in row-major order. A letter is only accepted if no
+/// word can be read through the cell using letters already in the grid; as
+/// every later cell is still empty at that point, this rules out every
+/// accidental match.
+fn fill(
+    config: &PuzzleConfig,
+    cells: &mut [Option<char>],
+    words: &[&str],
+    rng: &mut Rng,
+) -> Result<(), Box<dyn Error>> {
+    let words: Vec<Vec<char>> = words.iter().map(|word| word.chars().collect()).collect();
+
+    for idx in 0..cells.len() {
+        if cells[idx].is_some() {
+            continue;
+        }
+
+        let mut alphabet = config.alphabet.clone();
+        rng.shuffle(&mut alphabet);
+
+        let letter = alphabet.into_iter().find(|&c| {
+            cells[idx] = Some(c);
+            let free = !creates_match(config, cells, idx, &words);
+            cells[idx] = None;
+            free
+        });
+
+        match letter {
+            Some(c) => cells[idx] = Some(c),
+            None => return Err("Could not fill the grid without creating extra matches.".into()),
+        }
+    }
+
+    Ok(())
+}
+
+fn creates_match(
+    config: &PuzzleConfig,
+    cells: &[Option<char>],
+    idx: usize,
+    words: &[Vec<char>],
+) -> bool {
+    let cell = Position(
+        (idx / config.n).try_into().unwrap(),
+        (idx % config.n).try_into().unwrap(),
+    );
+
+    words.iter().any(|letters| {
+        Direction::ALL.iter().any(|&direction| {
+            (0..letters.len()).any(|offset| {
+                let start = cell.step(direction, -i32::try_from(offset).unwrap());
+                letters.iter().enumerate().all(|(i, c)| {
+                    let position = start.step(direction, i.try_into().unwrap());
+                    in_bounds(config, &position) && cells[index(config, &position)] == Some(*c)
+                })
+            })
+        })
+    })
+}
+
+fn in_bounds(config: &PuzzleConfig, position: &Position) -> bool {
+    (position.0 >= 0)
+        && (position.0 < config.m.try_into().unwrap())
+        && (position.1 >= 0)
+        && (position.1 < config.n.try_into().unwrap())
+}
+
+fn index(config: &PuzzleConfig, position: &Position) -> usize {
+    config.n * usize::try_from(position.0).unwrap() + usize::try_from(position.1).unwrap()
+}
+
+/// Small xorshift generator, so puzzles are reproducible from their seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // splitmix64 of the seed, so that nearby seeds give unrelated puzzles
+        // and a zero seed does not leave the xorshift state stuck at zero.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            values.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut matches: Vec<WordMatch>) -> Vec<WordMatch> {
+        matches.sort_by_key(|found| (found.start, found.direction, found.word.clone()));
+        matches
+    }
+
+    #[test]
+    fn check_generate_round_trip() {
+        let words: Vec<String> = ["XMAS", "SANTA", "ELF", "SLEIGH", "REINDEER", "TREE"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+        let word_refs: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        for (seed, overlap) in [
+            (1, Overlap::Forbid),
+            (2, Overlap::Allow),
+            (3, Overlap::Prefer),
+        ] {
+            let mut config = PuzzleConfig::new(10, 12, words.clone());
+            config.seed = seed;
+            config.overlap = overlap;
+            let puzzle = generate(&config).unwrap();
+
+            assert_eq!(puzzle.answers.len(), words.len());
+            assert_eq!(
+                sorted(puzzle.grid.find_words(&word_refs)),
+                sorted(puzzle.answers)
+            );
+        }
+    }
+
+    #[test]
+    fn check_generate_short_words() {
+        // Palindromes and one-letter words are found once, like any other.
+        let words = vec!["RADAR".to_string(), "X".to_string()];
+        for seed in 0..5 {
+            let mut config = PuzzleConfig::new(5, 5, words.clone());
+            config.seed = seed;
+            let puzzle = generate(&config).unwrap();
+
+            assert_eq!(puzzle.grid.find_words(&["RADAR", "X"]).len(), 2);
+        }
+    }
+
+    #[test]
+    fn check_generate_rejects_ambiguous_words() {
+        let nested = PuzzleConfig::new(5, 5, vec!["XMAS".to_string(), "SAM".to_string()]);
+        assert!(generate(&nested).is_err());
+    }
+}
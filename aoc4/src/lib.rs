@@ -0,0 +1,333 @@
+mod generate;
+mod matcher;
+mod render;
+mod stencil;
+
+use std::collections::HashSet;
+use std::fmt;
+
+pub use generate::{generate, Overlap, Puzzle, PuzzleConfig};
+use matcher::Matcher;
+pub use render::{render, render_ansi, render_blank, render_html, Highlight, RenderMode};
+pub use stencil::{Stencil, StencilMatch, Symmetry};
+
+/// The part two "X-MAS": two diagonal "MAS" crossing at their "A". Matched
+/// under all rotations, this covers every way of writing the two words.
+pub const X_MAS: &str = "\
+M.S
+.A.
+M.S";
+
+pub struct Grid {
+    pub m: usize,
+    pub n: usize,
+    pub values: Vec<char>,
+}
+
+impl Grid {
+    pub fn build(input: &str) -> Result<Grid, &'static str> {
+        let values: Vec<char> = input.lines().flat_map(|row| row.chars()).collect();
+        let m = input.lines().count();
+        let mut iter = input.lines().map(|row| row.chars().count());
+        let n = match iter.next() {
+            Some(x) => {
+                if iter.all(|elem| elem == x) {
+                    x
+                } else {
+                    return Err("Invalid input! Failed to construct grid.");
+                }
+            }
+            None => 0,
+        };
+
+        Ok(Self { m, n, values })
+    }
+
+    pub fn get(&self, position: &Position) -> Option<char> {
+        self.position_to_index(position).map(|idx| self.values[idx])
+    }
+
+    /// Finds every occurrence of every word in all eight directions. Each
+    /// line of the grid is scanned once per direction, regardless of how
+    /// many words are searched for.
+    ///
+    /// An occurrence is reported once even if it reads the same in several
+    /// directions, as one-letter words and palindromes do.
+    pub fn find_words(&self, words: &[&str]) -> Vec<WordMatch> {
+        let mut unique: Vec<&str> = Vec::new();
+        for &word in words {
+            if !unique.contains(&word) {
+                unique.push(word);
+            }
+        }
+        let matcher = Matcher::new(&unique);
+
+        let mut matches = Vec::new();
+        let mut seen = HashSet::new();
+
+        for direction in Direction::ALL {
+            for start in self.line_starts(direction) {
+                let line = self.line(start, direction);
+                for (end, word_idx) in matcher.find(line.iter().map(|&(_, c)| c)) {
+                    let first = end + 1 - matcher.word_len(word_idx);
+                    let found = WordMatch {
+                        start: line[first].0,
+                        direction,
+                        word: unique[word_idx].to_string(),
+                    };
+                    let mut cells = found.cells();
+                    cells.sort();
+                    if seen.insert((cells, word_idx)) {
+                        matches.push(found);
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// All cells, in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.values.len()).map(|idx| self.index_to_position(idx))
+    }
+
+    /// Cells where a line in `direction` enters the grid, i.e. cells whose
+    /// predecessor in that direction lies outside of it.
+    fn line_starts(&self, direction: Direction) -> Vec<Position> {
+        self.positions()
+            .filter(|position| !self.check_position(&position.step(direction, -1)))
+            .collect()
+    }
+
+    fn line(&self, start: Position, direction: Direction) -> Vec<(Position, char)> {
+        let mut line = Vec::new();
+        let mut position = start;
+        while let Some(c) = self.get(&position) {
+            line.push((position, c));
+            position = position.step(direction, 1);
+        }
+        line
+    }
+
+    fn index_to_position(&self, idx: usize) -> Position {
+        Position(
+            (idx / self.n).try_into().unwrap(),
+            (idx % self.n).try_into().unwrap(),
+        )
+    }
+
+    fn position_to_index(&self, position: &Position) -> Option<usize> {
+        match self.check_position(position) {
+            true => Some(
+                self.n * usize::try_from(position.0).unwrap()
+                    + usize::try_from(position.1).unwrap(),
+            ),
+            false => None,
+        }
+    }
+
+    fn check_position(&self, position: &Position) -> bool {
+        (position.0 < self.m.try_into().unwrap())
+            && (position.0 >= 0)
+            && (position.1 < self.n.try_into().unwrap())
+            && (position.1 >= 0)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = self
+            .values
+            .chunks(self.n.max(1))
+            .map(|row| row.iter().collect())
+            .collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WordMatch {
+    pub start: Position,
+    pub direction: Direction,
+    pub word: String,
+}
+
+impl WordMatch {
+    /// The cells covered by the match, from its first letter to its last.
+    pub fn cells(&self) -> Vec<Position> {
+        (0..self.word.chars().count())
+            .map(|i| self.start.step(self.direction, i.try_into().unwrap()))
+            .collect()
+    }
+}
+
+/// Row and column of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position(pub i32, pub i32);
+
+impl Position {
+    pub fn step(&self, direction: Direction, times: i32) -> Position {
+        let (di, dj) = direction.delta();
+        Position(self.0 + times * di, self.1 + times * dj)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    /// Change in (row, column) for one step in this direction.
+    pub fn delta(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::UpRight => (-1, 1),
+            Direction::Right => (0, 1),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (1, 0),
+            Direction::DownLeft => (1, -1),
+            Direction::Left => (0, -1),
+            Direction::UpLeft => (-1, -1),
+        }
+    }
+}
+
+pub struct Config {
+    pub file_path: String,
+    pub render: Option<RenderMode>,
+    pub words: Vec<String>,
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+        args.next();
+
+        let mut render = None;
+        let mut words = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--render" => match args.next() {
+                    Some(mode) => render = Some(RenderMode::parse(&mode)?),
+                    None => return Err("Did not get a render mode!"),
+                },
+                _ => words.push(arg),
+            }
+        }
+
+        if words.is_empty() {
+            words.push("XMAS".to_string());
+        }
+
+        Ok(Config {
+            file_path: "data.txt".to_string(),
+            render,
+            words,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX";
+
+    #[test]
+    fn check_find_words() {
+        let grid = Grid::build(EXAMPLE).unwrap();
+        assert_eq!(grid.find_words(&["XMAS"]).len(), 18);
+    }
+
+    #[test]
+    fn check_find_words_positions() {
+        let grid = Grid::build("XMAS\nMM..\nA.A.\nS..S").unwrap();
+        let mut matches = grid.find_words(&["XMAS", "MAS"]);
+        matches.sort_by_key(|found| (found.start, found.direction, found.word.clone()));
+
+        let expected = vec![
+            (Position(0, 0), Direction::Right, "XMAS"),
+            (Position(0, 0), Direction::DownRight, "XMAS"),
+            (Position(0, 0), Direction::Down, "XMAS"),
+            (Position(0, 1), Direction::Right, "MAS"),
+            (Position(1, 0), Direction::Down, "MAS"),
+            (Position(1, 1), Direction::DownRight, "MAS"),
+        ];
+        let found: Vec<_> = matches
+            .iter()
+            .map(|found| (found.start, found.direction, found.word.as_str()))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn check_find_words_once() {
+        let grid = Grid::build(EXAMPLE).unwrap();
+        let xs = grid.values.iter().filter(|&&c| c == 'X').count();
+        assert_eq!(grid.find_words(&["X"]).len(), xs);
+
+        let grid = Grid::build("ABA\n...").unwrap();
+        let matches = grid.find_words(&["ABA", "AB"]);
+        let found: Vec<_> = matches
+            .iter()
+            .map(|found| (found.start, found.direction, found.word.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Position(0, 0), Direction::Right, "AB"),
+                (Position(0, 0), Direction::Right, "ABA"),
+                (Position(0, 2), Direction::Left, "AB"),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_small_grids() {
+        let grid = Grid::build("XM\nAS").unwrap();
+        assert!(grid.find_words(&["XMAS"]).is_empty());
+        let x_mas = Stencil::parse(X_MAS).unwrap();
+        assert!(grid.find_stencil(&x_mas, Symmetry::Rotations).is_empty());
+
+        let empty = Grid::build("").unwrap();
+        assert!(empty.find_words(&["XMAS"]).is_empty());
+    }
+
+    #[test]
+    fn check_x_mas() {
+        let grid = Grid::build(EXAMPLE).unwrap();
+        let x_mas = Stencil::parse(X_MAS).unwrap();
+        assert_eq!(grid.find_stencil(&x_mas, Symmetry::Rotations).len(), 9);
+    }
+}
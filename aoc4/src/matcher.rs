@@ -0,0 +1,112 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Aho-Corasick automaton over `char`s, so any number of words can be found
+/// in a single pass over a line of the grid.
+pub struct Matcher {
+    transitions: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    outputs: Vec<Vec<usize>>,
+    lengths: Vec<usize>,
+}
+
+impl Matcher {
+    pub fn new(words: &[&str]) -> Matcher {
+        let mut matcher = Matcher {
+            transitions: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![Vec::new()],
+            lengths: Vec::with_capacity(words.len()),
+        };
+
+        for (word_idx, word) in words.iter().enumerate() {
+            matcher.lengths.push(word.chars().count());
+            if word.is_empty() {
+                continue;
+            }
+            let mut node = 0;
+            for c in word.chars() {
+                node = match matcher.transitions[node].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        matcher.transitions.push(HashMap::new());
+                        matcher.fail.push(0);
+                        matcher.outputs.push(Vec::new());
+                        let next = matcher.transitions.len() - 1;
+                        matcher.transitions[node].insert(c, next);
+                        next
+                    }
+                };
+            }
+            matcher.outputs[node].push(word_idx);
+        }
+
+        matcher.build_failure_links();
+        matcher
+    }
+
+    fn build_failure_links(&mut self) {
+        let mut queue: VecDeque<usize> = self.transitions[0].values().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> = self.transitions[node]
+                .iter()
+                .map(|(&c, &child)| (c, child))
+                .collect();
+
+            for (c, child) in children {
+                let mut fallback = self.fail[node];
+                while fallback != 0 && !self.transitions[fallback].contains_key(&c) {
+                    fallback = self.fail[fallback];
+                }
+                let target = match self.transitions[fallback].get(&c) {
+                    Some(&target) if target != child => target,
+                    _ => 0,
+                };
+                self.fail[child] = target;
+
+                let inherited = self.outputs[target].clone();
+                self.outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+
+    /// Length in characters of the word with the given index.
+    pub fn word_len(&self, word_idx: usize) -> usize {
+        self.lengths[word_idx]
+    }
+
+    /// Returns `(end, word_idx)` for every occurrence in `text`, where `end`
+    /// is the index of the last character of the occurrence.
+    pub fn find(&self, text: impl Iterator<Item = char>) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut node = 0;
+
+        for (idx, c) in text.enumerate() {
+            while node != 0 && !self.transitions[node].contains_key(&c) {
+                node = self.fail[node];
+            }
+            node = self.transitions[node].get(&c).copied().unwrap_or(0);
+
+            for &word_idx in &self.outputs[node] {
+                found.push((idx, word_idx));
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_find_overlapping() {
+        let matcher = Matcher::new(&["he", "she", "his", "hers"]);
+        let mut found = matcher.find("ushers".chars());
+        found.sort();
+
+        assert_eq!(found, vec![(3, 0), (3, 1), (5, 3)]);
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use crate::{Grid, Position, WordMatch};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Matched letters coloured with ANSI escape codes, one colour per word.
+    Ansi,
+    /// A standalone HTML page with one colour per word and a legend.
+    Html,
+    /// Every letter that is not part of a match replaced by `.`, like the
+    /// illustrations in the puzzle text.
+    Blank,
+}
+
+impl RenderMode {
+    pub fn parse(mode: &str) -> Result<RenderMode, &'static str> {
+        match mode.to_lowercase().as_str() {
+            "ansi" => Ok(RenderMode::Ansi),
+            "html" => Ok(RenderMode::Html),
+            "blank" => Ok(RenderMode::Blank),
+            _ => Err("Unknown render mode. Allowed modes: ['ansi', 'html', 'blank']"),
+        }
+    }
+}
+
+/// Something the renderers can highlight: a set of cells with a label that
+/// picks its colour and names it in the legend.
+pub trait Highlight {
+    fn label(&self) -> String;
+    fn cells(&self) -> Vec<Position>;
+}
+
+impl Highlight for WordMatch {
+    fn label(&self) -> String {
+        self.word.clone()
+    }
+
+    fn cells(&self) -> Vec<Position> {
+        WordMatch::cells(self)
+    }
+}
+
+/// Lets matches of different kinds be rendered together, as `&dyn Highlight`.
+impl<H: Highlight + ?Sized> Highlight for &H {
+    fn label(&self) -> String {
+        (**self).label()
+    }
+
+    fn cells(&self) -> Vec<Position> {
+        (**self).cells()
+    }
+}
+
+const ANSI_COLOURS: [&str; 6] = ["31", "32", "33", "34", "35", "36"];
+const HTML_COLOURS: [&str; 8] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+];
+const RESET: &str = "\x1b[0m";
+
+pub fn render<M: Highlight>(grid: &Grid, matches: &[M], mode: RenderMode) -> String {
+    match mode {
+        RenderMode::Ansi => render_ansi(grid, matches),
+        RenderMode::Html => render_html(grid, matches),
+        RenderMode::Blank => render_blank(grid, matches),
+    }
+}
+
+pub fn render_ansi<M: Highlight>(grid: &Grid, matches: &[M]) -> String {
+    let (_, cell_words) = colour_cells(grid, matches);
+
+    grid.values
+        .chunks(grid.n.max(1))
+        .zip(cell_words.chunks(grid.n.max(1)))
+        .map(|(row, row_words)| {
+            row.iter()
+                .zip(row_words)
+                .map(|(c, word)| match word {
+                    Some(word_idx) => format!(
+                        "\x1b[1;{}m{}{}",
+                        ANSI_COLOURS[word_idx % ANSI_COLOURS.len()],
+                        c,
+                        RESET
+                    ),
+                    None => c.to_string(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_html<M: Highlight>(grid: &Grid, matches: &[M]) -> String {
+    let (words, cell_words) = colour_cells(grid, matches);
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Word search</title>\n\
+         <style>\npre { font-size: 1.2em; }\n.unmatched { color: #aaaaaa; }\n</style>\n\
+         </head>\n<body>\n<ul>\n",
+    );
+    for (word_idx, word) in words.iter().enumerate() {
+        html.push_str(&format!(
+            "<li><span style=\"color: {}\">{}</span></li>\n",
+            HTML_COLOURS[word_idx % HTML_COLOURS.len()],
+            escape_html(word)
+        ));
+    }
+    html.push_str("</ul>\n<pre>\n");
+
+    for (row, row_words) in grid
+        .values
+        .chunks(grid.n.max(1))
+        .zip(cell_words.chunks(grid.n.max(1)))
+    {
+        for (c, word) in row.iter().zip(row_words) {
+            let letter = escape_html(&c.to_string());
+            match word {
+                Some(word_idx) => html.push_str(&format!(
+                    "<b style=\"color: {}\">{}</b>",
+                    HTML_COLOURS[word_idx % HTML_COLOURS.len()],
+                    letter
+                )),
+                None => html.push_str(&format!("<span class=\"unmatched\">{}</span>", letter)),
+            }
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n</body>\n</html>\n");
+
+    html
+}
+
+pub fn render_blank<M: Highlight>(grid: &Grid, matches: &[M]) -> String {
+    let (_, cell_words) = colour_cells(grid, matches);
+
+    grid.values
+        .chunks(grid.n.max(1))
+        .zip(cell_words.chunks(grid.n.max(1)))
+        .map(|(row, row_words)| {
+            row.iter()
+                .zip(row_words)
+                .map(|(&c, word)| if word.is_some() { c } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Assigns each distinct label a colour index in alphabetical order, and
+/// each cell the index of the first match covering it.
+fn colour_cells<M: Highlight>(grid: &Grid, matches: &[M]) -> (Vec<String>, Vec<Option<usize>>) {
+    let mut words: Vec<String> = matches.iter().map(Highlight::label).collect();
+    words.sort();
+    words.dedup();
+    let word_indices: HashMap<&str, usize> = words
+        .iter()
+        .enumerate()
+        .map(|(idx, word)| (word.as_str(), idx))
+        .collect();
+    let mut cell_words = vec![None; grid.values.len()];
+
+    for found in matches {
+        let word_idx = word_indices[found.label().as_str()];
+        for position in found.cells() {
+            if let Some(idx) = grid.position_to_index(&position) {
+                cell_words[idx].get_or_insert(word_idx);
+            }
+        }
+    }
+
+    (words, cell_words)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Stencil, Symmetry, X_MAS};
+
+    #[test]
+    fn check_render_blank() {
+        let input = "\
+..X...
+.SAMX.
+.A..A.
+XMAS.S
+.X....";
+        let grid = Grid::build(input).unwrap();
+        let matches = grid.find_words(&["XMAS"]);
+        assert_eq!(matches.len(), 4);
+        assert_eq!(render_blank(&grid, &matches), input);
+
+        let noisy = Grid::build("XMASQ\nQQQQQ").unwrap();
+        let matches = noisy.find_words(&["XMAS"]);
+        assert_eq!(render_blank(&noisy, &matches), "XMAS.\n.....");
+    }
+
+    #[test]
+    fn check_render_stencil() {
+        let grid = Grid::build("M.S.\n.A..\nM.SX\nXXXX").unwrap();
+        let x_mas = Stencil::parse(X_MAS).unwrap();
+        let matches = grid.find_stencil(&x_mas, Symmetry::Rotations);
+        assert_eq!(matches.len(), 1);
+
+        assert_eq!(
+            render(&grid, &matches, RenderMode::Blank),
+            "M.S.\n.A..\nM.S.\n...."
+        );
+        assert!(render_html(&grid, &matches).contains(">MSAMS</span>"));
+
+        let words = grid.find_words(&["XXXX"]);
+        let mixed: Vec<&dyn Highlight> = words
+            .iter()
+            .map(|found| found as &dyn Highlight)
+            .chain(matches.iter().map(|found| found as &dyn Highlight))
+            .collect();
+        assert_eq!(
+            render(&grid, &mixed, RenderMode::Blank),
+            "M.S.\n.A..\nM.S.\nXXXX"
+        );
+    }
+
+    #[test]
+    fn check_render_ansi() {
+        let grid = Grid::build("AB\nCD").unwrap();
+        let matches = grid.find_words(&["CD", "AB"]);
+        let rendered = render_ansi(&grid, &matches);
+
+        assert_eq!(
+            rendered,
+            "\x1b[1;31mA\x1b[0m\x1b[1;31mB\x1b[0m\n\x1b[1;32mC\x1b[0m\x1b[1;32mD\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Grid, Highlight, Position};
+
+/// A small 2D template of letters. Cells written as `.` are wildcards and
+/// match any letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil {
+    pub m: usize,
+    pub n: usize,
+    pub cells: Vec<Option<char>>,
+}
+
+/// Which transformations of a stencil are tried when matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    Exact,
+    Rotations,
+    RotationsAndReflections,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StencilMatch {
+    /// Grid position of the top-left cell of the placed template.
+    pub anchor: Position,
+    /// Index into [`Stencil::variants`] of the variant that matched.
+    pub variant: usize,
+    /// The non-wildcard cells of the placed variant with their letters, in
+    /// row-major order.
+    pub cells: Vec<(Position, char)>,
+}
+
+impl Highlight for StencilMatch {
+    /// The matched letters in row-major order, so each variant of a stencil
+    /// gets its own colour.
+    fn label(&self) -> String {
+        self.cells.iter().map(|&(_, c)| c).collect()
+    }
+
+    fn cells(&self) -> Vec<Position> {
+        self.cells.iter().map(|&(position, _)| position).collect()
+    }
+}
+
+impl Stencil {
+    pub const WILDCARD: char = '.';
+
+    pub fn parse(template: &str) -> Result<Stencil, &'static str> {
+        let m = template.lines().count();
+        let mut iter = template.lines().map(|row| row.chars().count());
+        let n = match iter.next() {
+            Some(x) if x > 0 => {
+                if iter.all(|elem| elem == x) {
+                    x
+                } else {
+                    return Err("Invalid template! Rows have different lengths.");
+                }
+            }
+            _ => return Err("Invalid template! Template is empty."),
+        };
+        let cells = template
+            .lines()
+            .flat_map(|row| row.chars())
+            .map(|c| (c != Self::WILDCARD).then_some(c))
+            .collect();
+
+        Ok(Stencil { m, n, cells })
+    }
+
+    /// The stencil turned 90 degrees clockwise.
+    pub fn rotate(&self) -> Stencil {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for i in 0..self.n {
+            for j in 0..self.m {
+                cells.push(self.cells[(self.m - 1 - j) * self.n + i]);
+            }
+        }
+        Stencil {
+            m: self.n,
+            n: self.m,
+            cells,
+        }
+    }
+
+    /// The stencil mirrored left to right.
+    pub fn reflect(&self) -> Stencil {
+        let cells = self
+            .cells
+            .chunks(self.n)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect();
+        Stencil {
+            m: self.m,
+            n: self.n,
+            cells,
+        }
+    }
+
+    /// The distinct templates matched under `symmetry`, starting with the
+    /// stencil itself. Symmetric stencils yield fewer variants, so a placement
+    /// is never counted twice.
+    pub fn variants(&self, symmetry: Symmetry) -> Vec<Stencil> {
+        let mut candidates = vec![self.clone()];
+        if symmetry != Symmetry::Exact {
+            for _ in 0..3 {
+                let next = candidates[candidates.len() - 1].rotate();
+                candidates.push(next);
+            }
+        }
+        if symmetry == Symmetry::RotationsAndReflections {
+            let reflected: Vec<Stencil> = candidates.iter().map(Stencil::reflect).collect();
+            candidates.extend(reflected);
+        }
+
+        let mut variants: Vec<Stencil> = Vec::new();
+        for candidate in candidates {
+            if !variants.contains(&candidate) {
+                variants.push(candidate);
+            }
+        }
+        variants
+    }
+
+    /// The non-wildcard cells with their letters when placed at `anchor`.
+    fn placed(&self, anchor: &Position) -> Vec<(Position, char)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, cell)| {
+                cell.map(|c| {
+                    let position = Position(
+                        anchor.0 + i32::try_from(idx / self.n).unwrap(),
+                        anchor.1 + i32::try_from(idx % self.n).unwrap(),
+                    );
+                    (position, c)
+                })
+            })
+            .collect()
+    }
+}
+
+impl Grid {
+    /// Every placement of the stencil (or one of its variants) that fits
+    /// inside the grid and agrees with it on all non-wildcard cells.
+    pub fn find_stencil(&self, stencil: &Stencil, symmetry: Symmetry) -> Vec<StencilMatch> {
+        let mut matches = Vec::new();
+
+        for (variant_idx, variant) in stencil.variants(symmetry).iter().enumerate() {
+            let (Some(max_i), Some(max_j)) =
+                (self.m.checked_sub(variant.m), self.n.checked_sub(variant.n))
+            else {
+                continue;
+            };
+            for i in 0..=max_i {
+                for j in 0..=max_j {
+                    let anchor = Position(i.try_into().unwrap(), j.try_into().unwrap());
+                    let cells = variant.placed(&anchor);
+                    if cells
+                        .iter()
+                        .all(|(position, c)| self.get(position) == Some(*c))
+                    {
+                        matches.push(StencilMatch {
+                            anchor,
+                            variant: variant_idx,
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_variants() {
+        let x_mas = Stencil::parse("M.S\n.A.\nM.S").unwrap();
+        assert_eq!(x_mas.variants(Symmetry::Exact).len(), 1);
+        assert_eq!(x_mas.variants(Symmetry::Rotations).len(), 4);
+        assert_eq!(x_mas.variants(Symmetry::RotationsAndReflections).len(), 4);
+
+        let corner = Stencil::parse("AB\nC.").unwrap();
+        assert_eq!(corner.rotate(), Stencil::parse("CA\n.B").unwrap());
+        assert_eq!(corner.reflect(), Stencil::parse("BA\n.C").unwrap());
+        assert_eq!(corner.variants(Symmetry::RotationsAndReflections).len(), 8);
+    }
+
+    #[test]
+    fn check_find_stencil() {
+        let grid = Grid::build("ABC\nDEF").unwrap();
+        let stencil = Stencil::parse("B.\n.F").unwrap();
+
+        assert_eq!(
+            grid.find_stencil(&stencil, Symmetry::Exact),
+            vec![StencilMatch {
+                anchor: Position(0, 1),
+                variant: 0,
+                cells: vec![(Position(0, 1), 'B'), (Position(1, 2), 'F')],
+            }]
+        );
+        assert!(grid
+            .find_stencil(&Stencil::parse("AB\nDE\nGH").unwrap(), Symmetry::Exact)
+            .is_empty());
+    }
+}
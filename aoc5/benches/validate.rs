@@ -0,0 +1,142 @@
+//! Compares update validation against the original string-set lookup on a
+//! synthetic input with one rule for every pair of 142 pages (10011 rules).
+//!
+//! Run with `cargo bench -p aoc5`.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+const PAGES: u32 = 142;
+const UPDATES: usize = 2000;
+const UPDATE_LEN: usize = 23;
+const ROUNDS: u32 = 5;
+
+fn main() {
+    let input = synthetic_input();
+
+    let (rules, updates) = aoc5::parse_input(&input).unwrap();
+    let (lookup, string_updates) = parse_strings(&input);
+    println!(
+        "{} rules, {} updates of {} pages",
+        input.lines().filter(|line| line.contains('|')).count(),
+        updates.len(),
+        UPDATE_LEN
+    );
+
+    let (old_valid, old_time) = time(|| {
+        string_updates
+            .iter()
+            .filter(|nums| check_valid(nums, &lookup))
+            .count()
+    });
+    let (new_valid, new_time) = time(|| {
+        updates
+            .iter()
+            .filter(|update| rules.is_valid(update))
+            .count()
+    });
+    assert_eq!(old_valid, new_valid);
+
+    println!("valid updates: {}", new_valid);
+    println!("string set lookup: {:>10.3?} per round", old_time);
+    println!("bitset adjacency:  {:>10.3?} per round", new_time);
+    println!(
+        "speedup: {:.1}x",
+        old_time.as_secs_f64() / new_time.as_secs_f64()
+    );
+}
+
+fn time(mut f: impl FnMut() -> usize) -> (usize, Duration) {
+    let result = f();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        assert_eq!(f(), result);
+    }
+    (result, start.elapsed() / ROUNDS)
+}
+
+/// Pages follow a hidden shuffled order with a rule for every pair; updates
+/// are random samples, half of them kept in the hidden order.
+fn synthetic_input() -> String {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut order: Vec<u32> = (10..10 + PAGES).collect();
+    for i in (1..order.len()).rev() {
+        order.swap(i, (next() % (i as u64 + 1)) as usize);
+    }
+
+    let mut input = String::new();
+    for (i, before) in order.iter().enumerate() {
+        for after in &order[i + 1..] {
+            input.push_str(&format!("{}|{}\n", before, after));
+        }
+    }
+    input.push('\n');
+
+    for update_idx in 0..UPDATES {
+        let mut positions: Vec<usize> = (0..order.len()).collect();
+        for i in 0..UPDATE_LEN {
+            let j = i + (next() % (positions.len() - i) as u64) as usize;
+            positions.swap(i, j);
+        }
+        let mut positions = positions[..UPDATE_LEN].to_vec();
+        if update_idx % 2 == 0 {
+            positions.sort();
+        }
+        let pages: Vec<String> = positions.iter().map(|&p| order[p].to_string()).collect();
+        input.push_str(&pages.join(","));
+        input.push('\n');
+    }
+
+    input
+}
+
+// The original implementation, kept here as the baseline.
+
+fn parse_strings(input: &str) -> (HashSet<(String, String)>, Vec<Vec<String>>) {
+    let (rules, pages) = input.split_once("\n\n").unwrap();
+    let rules = rules
+        .lines()
+        .map(|elem| match elem.split_once('|') {
+            Some((x, y)) => (x.to_string(), y.to_string()),
+            _ => ("".to_string(), "".to_string()),
+        })
+        .collect();
+    let pages = pages
+        .lines()
+        .map(|page| page.split(',').map(str::to_string).collect())
+        .collect();
+    (rules, pages)
+}
+
+fn check_valid(nums: &[String], lookup: &HashSet<(String, String)>) -> bool {
+    nums.iter()
+        .enumerate()
+        .all(|(i, _)| check_num(nums, lookup, i))
+}
+
+fn check_num(nums: &[String], lookup: &HashSet<(String, String)>, i: usize) -> bool {
+    let num = &nums[i];
+    let before = &nums[..i];
+    let after = &nums[i + 1..];
+
+    let after_zip = vec![num; after.len()].into_iter().zip(after.iter());
+    let before_zip = before.iter().zip(vec![num; before.len()]);
+
+    let after_b = after_zip
+        .filter(|elem| lookup.contains(&(elem.0.to_string(), elem.1.to_string())))
+        .count()
+        == after.len();
+    let before_b = before_zip
+        .filter(|elem| lookup.contains(&(elem.0.to_string(), elem.1.to_string())))
+        .count()
+        == before.len();
+
+    after_b && before_b
+}
@@ -0,0 +1,34 @@
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let mut file_path = "data.txt".to_string();
+    let mut dot = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--dot" => dot = true,
+            _ => file_path = arg,
+        }
+    }
+
+    let input = fs::read_to_string(&file_path).unwrap_or_else(|err| {
+        eprintln!("Problem Reading data: {err}");
+        process::exit(1);
+    });
+
+    let (rules, updates) = aoc5::split_sections(&input)
+        .and_then(|(rules, updates)| Ok((aoc5::parse_rules(rules)?, aoc5::parse_updates(updates)?)))
+        .unwrap_or_else(|err| {
+            eprintln!("Problem parsing input: {err}");
+            process::exit(1);
+        });
+
+    let report = aoc5::lint(&rules, &updates);
+
+    if dot {
+        print!("{}", aoc5::to_dot(&rules, &report));
+    } else {
+        print!("{}", report);
+    }
+}
@@ -0,0 +1,77 @@
+/// A growable set of small integers, one bit per value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> BitSet {
+        BitSet { words: Vec::new() }
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        let (word, bit) = (value / 64, value % 64);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << bit;
+    }
+
+    pub fn remove(&mut self, value: usize) {
+        if let Some(word) = self.words.get_mut(value / 64) {
+            *word &= !(1 << (value % 64));
+        }
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.words
+            .get(value / 64)
+            .is_some_and(|word| word & (1 << (value % 64)) != 0)
+    }
+
+    /// Whether the two sets have at least one value in common.
+    pub fn intersects(&self, other: &BitSet) -> bool {
+        self.words.iter().zip(&other.words).any(|(a, b)| a & b != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                Some(idx * 64 + bit)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_bitset() {
+        let mut set = BitSet::new();
+        set.insert(3);
+        set.insert(64);
+        set.insert(200);
+
+        assert!(set.contains(3) && set.contains(64) && set.contains(200));
+        assert!(!set.contains(4) && !set.contains(1000));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 64, 200]);
+
+        set.remove(64);
+        set.remove(1000);
+        assert!(!set.contains(64));
+
+        let mut other = BitSet::new();
+        other.insert(65);
+        assert!(!set.intersects(&other));
+        other.insert(200);
+        assert!(set.intersects(&other));
+    }
+}
@@ -0,0 +1,437 @@
+mod bitset;
+mod lint;
+mod moves;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use bitset::BitSet;
+pub use lint::{lint, to_dot, LintReport};
+pub use moves::{PageMove, Swap, MAX_UNORDERED};
+
+/// The pages of one update, in printing order.
+pub type Update = Vec<u32>;
+
+/// A rule `(a, b)`, written `a|b`: page `a` must come before page `b`.
+pub type Rule = (u32, u32);
+
+pub fn parse_input(input: &str) -> Result<(Rules, Vec<Update>), Box<dyn Error>> {
+    let (rules, updates) = split_sections(input)?;
+    Ok((Rules::build(rules)?, parse_updates(updates)?))
+}
+
+/// Splits the input into the rules section and the updates section.
+pub fn split_sections(input: &str) -> Result<(&str, &str), Box<dyn Error>> {
+    match input.split_once("\n\n") {
+        Some((x, y)) => Ok((x, y)),
+        None => Err("Expected rules and updates separated by an empty line.".into()),
+    }
+}
+
+/// Parses the rules section as written, keeping duplicates and order.
+pub fn parse_rules(input: &str) -> Result<Vec<Rule>, Box<dyn Error>> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once('|') {
+            Some((x, y)) => Ok((x.trim().parse::<u32>()?, y.trim().parse::<u32>()?)),
+            None => Err(format!("Expected a rule of the form 'X|Y'. Got {}.", line).into()),
+        })
+        .collect()
+}
+
+pub fn parse_updates(input: &str) -> Result<Vec<Update>, Box<dyn Error>> {
+    let updates = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|page| page.trim().parse::<u32>())
+                .collect::<Result<Update, _>>()
+        })
+        .collect::<Result<Vec<Update>, _>>()?;
+
+    Ok(updates)
+}
+
+/// The page ordering rules as a directed graph: an edge `a -> b` means page
+/// `a` must be printed before page `b` whenever both are in an update.
+///
+/// Pages are interned to dense ids, and the successors of each page are kept
+/// as a bitset over those ids.
+pub struct Rules {
+    ids: HashMap<u32, usize>,
+    pages: Vec<u32>,
+    successors: Vec<BitSet>,
+}
+
+impl Rules {
+    pub fn build(input: &str) -> Result<Rules, Box<dyn Error>> {
+        Ok(Rules::from_rules(&parse_rules(input)?))
+    }
+
+    pub fn from_rules(rules: &[Rule]) -> Rules {
+        let mut graph = Rules::new();
+        for &(before, after) in rules {
+            graph.add_rule(before, after);
+        }
+
+        graph
+    }
+
+    pub fn new() -> Rules {
+        Rules {
+            ids: HashMap::new(),
+            pages: Vec::new(),
+            successors: Vec::new(),
+        }
+    }
+
+    pub fn add_rule(&mut self, before: u32, after: u32) {
+        let before = self.intern(before);
+        let after = self.intern(after);
+        self.successors[before].insert(after);
+    }
+
+    /// Removes the rule `before|after`, returning whether it was present.
+    pub fn remove_rule(&mut self, before: u32, after: u32) -> bool {
+        let present = self.has_rule(before, after);
+        if present {
+            self.successors[self.ids[&before]].remove(self.ids[&after]);
+        }
+        present
+    }
+
+    fn intern(&mut self, page: u32) -> usize {
+        match self.ids.get(&page) {
+            Some(&id) => id,
+            None => {
+                let id = self.pages.len();
+                self.ids.insert(page, id);
+                self.pages.push(page);
+                self.successors.push(BitSet::new());
+                id
+            }
+        }
+    }
+
+    fn successors_of(&self, page: u32) -> impl Iterator<Item = u32> + '_ {
+        self.ids
+            .get(&page)
+            .into_iter()
+            .flat_map(|&id| self.successors[id].iter())
+            .map(|id| self.pages[id])
+    }
+
+    /// Whether the rule `before|after` is part of the rule set.
+    pub fn has_rule(&self, before: u32, after: u32) -> bool {
+        match (self.ids.get(&before), self.ids.get(&after)) {
+            (Some(&before), Some(&after)) => self.successors[before].contains(after),
+            _ => false,
+        }
+    }
+
+    /// Whether `before` must come before `after`, either by a rule of its own
+    /// or through a chain of rules.
+    pub fn must_precede(&self, before: u32, after: u32) -> bool {
+        let (Some(&start), Some(&target)) = (self.ids.get(&before), self.ids.get(&after)) else {
+            return false;
+        };
+
+        let mut visited = BitSet::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            for next in self.successors[id].iter() {
+                if next == target {
+                    return true;
+                }
+                if !visited.contains(next) {
+                    visited.insert(next);
+                    stack.push(next);
+                }
+            }
+        }
+
+        false
+    }
+
+    /// An update is valid if no rule asks for a later page to come before an
+    /// earlier one.
+    pub fn is_valid(&self, update: &[u32]) -> bool {
+        self.check(update).is_ok()
+    }
+
+    /// Checks the update in one pass: each page's successors must not
+    /// intersect the set of pages printed before it. On failure, names the
+    /// rule broken by the first offending page.
+    pub fn check(&self, update: &[u32]) -> Result<(), Violation> {
+        let mut printed = BitSet::new();
+
+        for (position, page) in update.iter().enumerate() {
+            if let Some(&id) = self.ids.get(page) {
+                if self.successors[id].intersects(&printed) {
+                    let earlier = update[..position]
+                        .iter()
+                        .position(|&earlier| self.has_rule(*page, earlier))
+                        .unwrap();
+                    return Err(Violation {
+                        rule: (*page, update[earlier]),
+                        positions: (position, earlier),
+                    });
+                }
+                printed.insert(id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reorders the update by a topological sort of the rules restricted to
+    /// its pages. Fails if the rules between those pages contain a cycle, or
+    /// do not determine a single order.
+    pub fn repair(&self, update: &[u32]) -> Result<Vec<u32>, OrderError> {
+        let mut in_degree: HashMap<u32, usize> = HashMap::new();
+        for &page in update {
+            if in_degree.insert(page, 0).is_some() {
+                return Err(OrderError::DuplicatePage(page));
+            }
+        }
+        for &before in update {
+            for &after in update {
+                if self.has_rule(before, after) {
+                    *in_degree.get_mut(&after).unwrap() += 1;
+                }
+            }
+        }
+
+        let mut remaining: Vec<u32> = update.to_vec();
+        let mut fixed = Vec::with_capacity(update.len());
+
+        while !remaining.is_empty() {
+            let ready: Vec<u32> = remaining
+                .iter()
+                .copied()
+                .filter(|page| in_degree[page] == 0)
+                .collect();
+
+            match ready[..] {
+                [] => return Err(OrderError::Cycle(self.find_cycle(&remaining))),
+                [page] => {
+                    remaining.retain(|&other| other != page);
+                    for &after in &remaining {
+                        if self.has_rule(page, after) {
+                            *in_degree.get_mut(&after).unwrap() -= 1;
+                        }
+                    }
+                    fixed.push(page);
+                }
+                [first, second, ..] => return Err(OrderError::Unordered(first, second)),
+            }
+        }
+
+        Ok(fixed)
+    }
+
+    /// Finds a cycle among `pages`, each of which has a predecessor among
+    /// them. Walking backwards along rules must eventually revisit a page.
+    fn find_cycle(&self, pages: &[u32]) -> Vec<u32> {
+        let mut path = vec![pages[0]];
+
+        loop {
+            let current = path[path.len() - 1];
+            let predecessor = pages
+                .iter()
+                .copied()
+                .find(|&page| self.has_rule(page, current))
+                .unwrap();
+
+            if let Some(start) = path.iter().position(|&page| page == predecessor) {
+                let mut cycle = path[start..].to_vec();
+                cycle.reverse();
+                return cycle;
+            }
+            path.push(predecessor);
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::new()
+    }
+}
+
+/// A rule broken by an update: `rule.0` is printed at `positions.0`, after
+/// `rule.1` at `positions.1`, although the rule puts it first.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub positions: (usize, usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "page {} at position {} comes after page {} at position {}, breaking rule {}|{}",
+            self.rule.0, self.positions.0, self.rule.1, self.positions.1, self.rule.0, self.rule.1
+        )
+    }
+}
+
+impl Error for Violation {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrderError {
+    /// The rules between the pages of the update form this cycle.
+    Cycle(Vec<u32>),
+    /// No rule between pages of the update, directly or through other pages
+    /// of the update, decides which of these pages comes first.
+    Unordered(u32, u32),
+    /// The page appears more than once in the update.
+    DuplicatePage(u32),
+    /// This many pages of the update are pairwise unordered, too many to
+    /// search for the closest order the rules allow.
+    TooManyUnordered(usize),
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::Cycle(pages) => {
+                let cycle: Vec<String> = pages
+                    .iter()
+                    .chain(pages.first())
+                    .map(|page| page.to_string())
+                    .collect();
+                write!(f, "Rules form a cycle: {}", cycle.join(" -> "))
+            }
+            OrderError::Unordered(first, second) => {
+                write!(
+                    f,
+                    "No rule decides the order of pages {} and {}",
+                    first, second
+                )
+            }
+            OrderError::DuplicatePage(page) => write!(f, "Page {} appears more than once", page),
+            OrderError::TooManyUnordered(count) => {
+                write!(f, "{} pages are unordered, too many to search", count)
+            }
+        }
+    }
+}
+
+impl Error for OrderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13
+
+75,47,61,53,29
+97,61,53,29,13
+75,29,13
+75,97,47,61,53
+61,13,29
+97,13,75,29,47";
+
+    #[test]
+    fn check_middle_pages() {
+        let (rules, updates) = parse_input(EXAMPLE).unwrap();
+        let mut total_mid = 0;
+        let mut total_mid_wrong = 0;
+
+        for update in updates {
+            if rules.is_valid(&update) {
+                total_mid += update[update.len() / 2];
+            } else {
+                total_mid_wrong += rules.repair(&update).unwrap()[update.len() / 2];
+            }
+        }
+
+        assert_eq!(total_mid, 143);
+        assert_eq!(total_mid_wrong, 123);
+    }
+
+    #[test]
+    fn check_repair() {
+        let (rules, _) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(
+            rules.repair(&[97, 13, 75, 29, 47]).unwrap(),
+            vec![97, 75, 47, 29, 13]
+        );
+    }
+
+    #[test]
+    fn check_violation() {
+        let (rules, _) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(rules.check(&[75, 47, 61, 53, 29]), Ok(()));
+        assert_eq!(
+            rules.check(&[61, 13, 29]),
+            Err(Violation {
+                rule: (29, 13),
+                positions: (2, 1),
+            })
+        );
+    }
+
+    #[test]
+    fn check_incremental_rules() {
+        let mut rules = Rules::new();
+        rules.add_rule(1, 2);
+        rules.add_rule(2, 3);
+
+        assert!(rules.has_rule(1, 2) && !rules.has_rule(1, 3));
+        assert!(rules.must_precede(1, 3));
+        assert!(!rules.must_precede(3, 1));
+        assert!(!rules.must_precede(1, 42));
+        assert!(rules.is_valid(&[3, 1]));
+
+        rules.add_rule(3, 1);
+        assert!(!rules.is_valid(&[1, 3]));
+        assert!(rules.must_precede(3, 2));
+
+        assert!(rules.remove_rule(2, 3));
+        assert!(!rules.remove_rule(2, 3));
+        assert!(!rules.must_precede(1, 3));
+        assert_eq!(rules.repair(&[1, 3, 2]), Ok(vec![3, 1, 2]));
+    }
+
+    #[test]
+    fn check_repair_errors() {
+        let rules = Rules::build("1|2\n2|3\n3|1\n4|5").unwrap();
+        assert_eq!(
+            rules.repair(&[3, 4, 1, 2]),
+            Err(OrderError::Cycle(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            rules.repair(&[2, 3, 1]),
+            Err(OrderError::Cycle(vec![3, 1, 2]))
+        );
+        assert_eq!(rules.repair(&[5, 6]), Err(OrderError::Unordered(5, 6)));
+        assert_eq!(rules.repair(&[5, 5]), Err(OrderError::DuplicatePage(5)));
+    }
+}
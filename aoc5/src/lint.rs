@@ -0,0 +1,403 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::{Rule, Rules, Update};
+
+/// Problems found in a hand-maintained rule set.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// Rules listed more than once.
+    pub duplicates: Vec<Rule>,
+    /// Pairs of pages ordered both ways, `a|b` as well as `b|a`.
+    pub contradictions: Vec<Rule>,
+    /// One cycle per group of pages whose rules cannot all be satisfied,
+    /// other than the two-page cycles already listed as contradictions.
+    pub cycles: Vec<Vec<u32>>,
+    /// Rules that follow from a chain of other rules.
+    pub redundant: Vec<Rule>,
+    /// Pages that appear in rules but in no update.
+    pub unused_pages: Vec<u32>,
+}
+
+impl LintReport {
+    pub fn is_clean(&self) -> bool {
+        self.duplicates.is_empty()
+            && self.contradictions.is_empty()
+            && self.cycles.is_empty()
+            && self.redundant.is_empty()
+            && self.unused_pages.is_empty()
+    }
+}
+
+pub fn lint(rules: &[Rule], updates: &[Update]) -> LintReport {
+    let graph = Rules::from_rules(rules);
+    let unique: BTreeSet<Rule> = rules.iter().copied().collect();
+
+    let mut seen = HashSet::new();
+    let mut duplicates = BTreeSet::new();
+    for &rule in rules {
+        if !seen.insert(rule) {
+            duplicates.insert(rule);
+        }
+    }
+
+    let contradictions = unique
+        .iter()
+        .copied()
+        .filter(|&(before, after)| before < after && unique.contains(&(after, before)))
+        .collect();
+
+    let mut cycles: Vec<Vec<u32>> = unique
+        .iter()
+        .filter(|(before, after)| before == after)
+        .map(|&(page, _)| vec![page])
+        .collect();
+    for component in strongly_connected_components(&graph) {
+        if component.len() > 2 {
+            cycles.extend(long_cycle(&graph, &component));
+        }
+    }
+    cycles.sort();
+
+    let redundant = unique
+        .iter()
+        .copied()
+        .filter(|&(before, after)| before != after && reachable_without(&graph, before, after))
+        .collect();
+
+    let mentioned: HashSet<u32> = updates.iter().flatten().copied().collect();
+    let unused_pages = pages(rules)
+        .into_iter()
+        .filter(|page| !mentioned.contains(page))
+        .collect();
+
+    LintReport {
+        duplicates: duplicates.into_iter().collect(),
+        contradictions,
+        cycles,
+        redundant,
+        unused_pages,
+    }
+}
+
+/// Whether `to` can be reached from `from` along rules other than the direct
+/// rule `from|to`.
+fn reachable_without(graph: &Rules, from: u32, to: u32) -> bool {
+    let mut visited = HashSet::from([from]);
+    let mut queue: VecDeque<u32> = graph
+        .successors_of(from)
+        .filter(|&page| page != to)
+        .collect();
+
+    while let Some(page) = queue.pop_front() {
+        if page == to {
+            return true;
+        }
+        if visited.insert(page) {
+            queue.extend(graph.successors_of(page));
+        }
+    }
+
+    false
+}
+
+/// A cycle of at least three pages within a strongly connected
+/// `component`, if its rules form one and not only two-page cycles.
+fn long_cycle(graph: &Rules, component: &[u32]) -> Option<Vec<u32>> {
+    let cycle = graph.find_cycle(component);
+    if cycle.len() > 2 {
+        return Some(cycle);
+    }
+
+    // Look for the shortest way back to `start` from one of its successors
+    // that does not use the direct rule back to it; together with the rule
+    // to that successor it closes a cycle of three pages or more.
+    let members: HashSet<u32> = component.iter().copied().collect();
+    for &start in component {
+        for first in graph.successors_of(start) {
+            if !members.contains(&first) || first == start {
+                continue;
+            }
+
+            let mut parents = HashMap::from([(first, first)]);
+            let mut queue = VecDeque::from([first]);
+            while let Some(page) = queue.pop_front() {
+                for next in graph.successors_of(page) {
+                    if !members.contains(&next)
+                        || (page == first && next == start)
+                        || parents.contains_key(&next)
+                    {
+                        continue;
+                    }
+                    if next == start {
+                        let mut cycle = vec![page];
+                        while cycle[cycle.len() - 1] != first {
+                            cycle.push(parents[&cycle[cycle.len() - 1]]);
+                        }
+                        cycle.push(start);
+                        cycle.reverse();
+                        return Some(cycle);
+                    }
+                    parents.insert(next, page);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Tarjan's algorithm, with an explicit stack of the pages being visited so
+/// that long chains of rules cannot overflow the call stack. Components are
+/// returned with their pages sorted.
+fn strongly_connected_components(graph: &Rules) -> Vec<Vec<u32>> {
+    struct State {
+        index: HashMap<u32, usize>,
+        low_link: HashMap<u32, usize>,
+        stack: Vec<u32>,
+        on_stack: HashSet<u32>,
+        /// Pages being visited, each with the successors still to look at.
+        visiting: Vec<(u32, Vec<u32>)>,
+        components: Vec<Vec<u32>>,
+    }
+
+    fn enter(graph: &Rules, page: u32, state: &mut State) {
+        let index = state.index.len();
+        state.index.insert(page, index);
+        state.low_link.insert(page, index);
+        state.stack.push(page);
+        state.on_stack.insert(page);
+        state
+            .visiting
+            .push((page, graph.successors_of(page).collect()));
+    }
+
+    fn leave(page: u32, state: &mut State) {
+        if let Some(&(parent, _)) = state.visiting.last() {
+            let low = state.low_link[&parent].min(state.low_link[&page]);
+            state.low_link.insert(parent, low);
+        }
+
+        if state.low_link[&page] == state.index[&page] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(&member);
+                component.push(member);
+                if member == page {
+                    break;
+                }
+            }
+            component.sort();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        visiting: Vec::new(),
+        components: Vec::new(),
+    };
+
+    let mut all_pages: Vec<u32> = graph.pages.clone();
+    all_pages.sort();
+    for root in all_pages {
+        if state.index.contains_key(&root) {
+            continue;
+        }
+
+        enter(graph, root, &mut state);
+        while let Some((page, successors)) = state.visiting.last_mut() {
+            let page = *page;
+            match successors.pop() {
+                Some(next) if !state.index.contains_key(&next) => enter(graph, next, &mut state),
+                Some(next) => {
+                    if state.on_stack.contains(&next) {
+                        let low = state.low_link[&page].min(state.index[&next]);
+                        state.low_link.insert(page, low);
+                    }
+                }
+                None => {
+                    state.visiting.pop();
+                    leave(page, &mut state);
+                }
+            }
+        }
+    }
+
+    state.components
+}
+
+fn pages(rules: &[Rule]) -> BTreeSet<u32> {
+    rules
+        .iter()
+        .flat_map(|&(before, after)| [before, after])
+        .collect()
+}
+
+/// Renders the rule graph in Graphviz DOT. Rules involved in a contradiction
+/// or cycle are drawn in red, redundant rules dashed and grey, and pages no
+/// update uses are drawn grey.
+pub fn to_dot(rules: &[Rule], report: &LintReport) -> String {
+    let mut flagged: HashSet<Rule> = HashSet::new();
+    for &(before, after) in &report.contradictions {
+        flagged.insert((before, after));
+        flagged.insert((after, before));
+    }
+    for cycle in &report.cycles {
+        for (i, &page) in cycle.iter().enumerate() {
+            flagged.insert((page, cycle[(i + 1) % cycle.len()]));
+        }
+    }
+    let redundant: HashSet<Rule> = report.redundant.iter().copied().collect();
+
+    let mut dot = String::from("digraph rules {\n");
+    for page in &report.unused_pages {
+        dot.push_str(&format!("    {} [color=grey, fontcolor=grey];\n", page));
+    }
+
+    let mut counts: BTreeMap<Rule, usize> = BTreeMap::new();
+    for &rule in rules {
+        *counts.entry(rule).or_default() += 1;
+    }
+    for (rule, count) in counts {
+        let mut attributes = Vec::new();
+        if flagged.contains(&rule) {
+            attributes.push("color=red".to_string());
+        } else if redundant.contains(&rule) {
+            attributes.push("color=grey, style=dashed".to_string());
+        }
+        if count > 1 {
+            attributes.push(format!("label=\"x{}\"", count));
+        }
+
+        dot.push_str(&format!("    {} -> {}", rule.0, rule.1));
+        if !attributes.is_empty() {
+            dot.push_str(&format!(" [{}]", attributes.join(", ")));
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "No problems found.");
+        }
+
+        let format_rules = |rules: &[Rule]| {
+            rules
+                .iter()
+                .map(|(before, after)| format!("{}|{}", before, after))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        if !self.duplicates.is_empty() {
+            writeln!(f, "Duplicate rules: {}", format_rules(&self.duplicates))?;
+        }
+        for &(before, after) in &self.contradictions {
+            writeln!(
+                f,
+                "Contradiction: {}|{} and {}|{}",
+                before, after, after, before
+            )?;
+        }
+        for cycle in &self.cycles {
+            let pages: Vec<String> = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|page| page.to_string())
+                .collect();
+            writeln!(f, "Cycle: {}", pages.join(" -> "))?;
+        }
+        if !self.redundant.is_empty() {
+            writeln!(
+                f,
+                "Implied by other rules: {}",
+                format_rules(&self.redundant)
+            )?;
+        }
+        if !self.unused_pages.is_empty() {
+            let pages: Vec<String> = self
+                .unused_pages
+                .iter()
+                .map(|page| page.to_string())
+                .collect();
+            writeln!(f, "Pages in no update: {}", pages.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_rules, parse_updates};
+
+    #[test]
+    fn check_lint() {
+        let rules = parse_rules("1|2\n2|3\n1|3\n1|2\n4|5\n5|4\n6|7\n7|8\n8|6").unwrap();
+        let updates = parse_updates("1,2,3\n4,5").unwrap();
+        let report = lint(&rules, &updates);
+
+        assert_eq!(report.duplicates, vec![(1, 2)]);
+        assert_eq!(report.contradictions, vec![(4, 5)]);
+        assert_eq!(report.cycles, vec![vec![7, 8, 6]]);
+        assert_eq!(report.redundant, vec![(1, 3)]);
+        assert_eq!(report.unused_pages, vec![6, 7, 8]);
+    }
+
+    #[test]
+    fn check_lint_two_page_cycles() {
+        // Held together only by contradictions, which are not repeated as
+        // a cycle.
+        let rules = parse_rules("1|2\n2|1\n2|3\n3|2").unwrap();
+        let report = lint(&rules, &[]);
+        assert_eq!(report.contradictions, vec![(1, 2), (2, 3)]);
+        assert!(report.cycles.is_empty());
+        assert!(!report.to_string().contains("Cycle"));
+
+        // A longer cycle through a contradiction is still found.
+        let rules = parse_rules("1|2\n2|1\n2|3\n3|1").unwrap();
+        let report = lint(&rules, &[]);
+        assert_eq!(report.contradictions, vec![(1, 2)]);
+        assert_eq!(report.cycles, vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn check_lint_long_chain() {
+        // Deep enough to overflow the stack of a recursive search.
+        let rules: Vec<Rule> = (0..30_000).map(|page| (page, page + 1)).collect();
+        let report = lint(&rules, &[]);
+
+        assert!(report.cycles.is_empty());
+        assert!(report.redundant.is_empty());
+        assert_eq!(report.unused_pages.len(), 30_001);
+    }
+
+    #[test]
+    fn check_to_dot() {
+        let rules = parse_rules("1|2\n2|3\n1|3\n1|2").unwrap();
+        let updates = parse_updates("1,2").unwrap();
+        let dot = to_dot(&rules, &lint(&rules, &updates));
+
+        assert_eq!(
+            dot,
+            "digraph rules {
+    3 [color=grey, fontcolor=grey];
+    1 -> 2 [label=\"x2\"];
+    1 -> 3 [color=grey, style=dashed];
+    2 -> 3;
+}
+"
+        );
+    }
+}
@@ -1,81 +1,75 @@
-use std::collections::HashSet;
+use std::env;
 use std::fs;
+use std::process;
 
 fn main() {
-    let input = fs::read_to_string("data.txt").unwrap();
-    let (rules, pages) = match input.split_once("\n\n") {
-        Some((x, y)) => (x.to_string(), y.to_string()),
-        _ => ("".to_string(), "".to_string()),
-    };
+    let show_moves = env::args().skip(1).any(|arg| arg == "--moves");
 
-    let rules = rules
-        .replace("|", ",")
-        .split("\n")
-        .map(|elem| match elem.split_once(",") {
-            Some((x, y)) => (x.to_string(), y.to_string()),
-            _ => ("".to_string(), "".to_string()),
-        })
-        .collect::<HashSet<(String, String)>>();
-    let pages = pages.lines().map(str::to_string).collect::<Vec<String>>();
+    let input = fs::read_to_string("data.txt").unwrap_or_else(|err| {
+        eprintln!("Problem Reading data: {err}");
+        process::exit(1);
+    });
+
+    let (rules, updates) = aoc5::parse_input(&input).unwrap_or_else(|err| {
+        eprintln!("Problem parsing input: {err}");
+        process::exit(1);
+    });
 
     let mut total_mid: u32 = 0;
     let mut total_mid_wrong: u32 = 0;
+    let mut total_moves: usize = 0;
+    let mut total_swaps: usize = 0;
+
+    for update in updates {
+        if rules.is_valid(&update) {
+            total_mid += update[update.len() / 2];
+            continue;
+        }
+
+        // The rules may leave some pages unordered: the update can still be
+        // fixed, but has no single middle page.
+        match rules.repair(&update) {
+            Ok(fixed) => total_mid_wrong += fixed[fixed.len() / 2],
+            Err(err) => eprintln!("Problem repairing update {:?}: {err}", update),
+        }
+
+        let (moves, swaps) = match rules
+            .minimum_moves(&update)
+            .and_then(|moves| Ok((moves, rules.minimum_swaps(&update)?)))
+        {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Problem counting moves for update {:?}: {err}", update);
+                continue;
+            }
+        };
 
-    for page in pages {
-        let nums: Vec<String> = page.split(",").map(str::to_string).collect();
+        total_moves += moves.len();
+        total_swaps += swaps.len();
 
-        if check_valid(&nums, &rules) {
-            total_mid += nums[nums.len() / 2].parse::<u32>().unwrap();
-        } else {
-            total_mid_wrong += fix_page(&nums, &rules)[nums.len() / 2]
-                .parse::<u32>()
-                .unwrap();
+        if show_moves {
+            let moves: Vec<String> = moves
+                .iter()
+                .map(|page_move| page_move.to_string())
+                .collect();
+            println!(
+                "{:?}: {} moves ({}), {} swaps",
+                update,
+                moves.len(),
+                moves.join(", "),
+                swaps.len()
+            );
         }
     }
+
     println!("Sum of middle page numbers: {}", total_mid);
     println!(
         "Sum of middle page numbers (wrong updates): {}",
         total_mid_wrong
     );
-}
-
-fn fix_page(nums: &Vec<String>, lookup: &HashSet<(String, String)>) -> Vec<String> {
-    let mut fixed_nums: Vec<String> = vec!["".to_string(); nums.len()];
-
-    for num in nums {
-        let n_after = nums
-            .iter()
-            .filter(|elem| lookup.contains(&(num.to_string(), elem.to_string())) && elem != &num)
-            .count();
-        let fixed_index = nums.len() - 1 - n_after;
-        fixed_nums[fixed_index] = num.to_string();
-    }
-
-    fixed_nums
-}
-
-fn check_valid(nums: &Vec<String>, lookup: &HashSet<(String, String)>) -> bool {
-    nums.iter()
-        .enumerate()
-        .all(|(i, _)| check_num(nums, lookup, i))
-}
-
-fn check_num(nums: &Vec<String>, lookup: &HashSet<(String, String)>, i: usize) -> bool {
-    let num = &nums[i];
-    let before = &nums[..i];
-    let after = &nums[i + 1..];
-
-    let after_zip = vec![num; after.len()].into_iter().zip(after.into_iter());
-    let before_zip = before.into_iter().zip(vec![num; before.len()].into_iter());
-
-    let after_b = after_zip
-        .filter(|elem| lookup.contains(&(elem.0.to_string(), elem.1.to_string())))
-        .count()
-        == after.len();
-    let before_b = before_zip
-        .filter(|elem| lookup.contains(&(elem.0.to_string(), elem.1.to_string())))
-        .count()
-        == before.len();
-
-    after_b && before_b
+    println!("Minimum page moves to fix wrong updates: {}", total_moves);
+    println!(
+        "Minimum adjacent swaps to fix wrong updates: {}",
+        total_swaps
+    );
 }
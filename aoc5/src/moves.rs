@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::{OrderError, Rules};
+
+/// Take the page at index `from` out of the update and reinsert it so that it
+/// ends up at index `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageMove {
+    pub page: u32,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Exchange the pages at `index` and `index + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Swap {
+    pub index: usize,
+    pub pages: (u32, u32),
+}
+
+impl fmt::Display for PageMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.page, self.from, self.to)
+    }
+}
+
+impl fmt::Display for Swap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "swap {} and {} at {}",
+            self.pages.0, self.pages.1, self.index
+        )
+    }
+}
+
+impl Rules {
+    /// The fewest single-page moves that turn the update into an order the
+    /// rules allow. The largest set of pages that the rules let stay in
+    /// their current relative order stays put; every other page is moved
+    /// once. The rules need not decide the order of every pair of pages.
+    pub fn minimum_moves(&self, update: &[u32]) -> Result<Vec<PageMove>, OrderError> {
+        let precedes = self.precedence(update)?;
+        let settled = largest_consistent_subset(&precedes);
+        let rank = ranks(update, &target_order(&precedes, &settled));
+
+        let mut current: Vec<(u32, bool)> = update.iter().copied().zip(settled).collect();
+        let mut moves = Vec::new();
+
+        for &page in update {
+            let from = current
+                .iter()
+                .position(|&(other, _)| other == page)
+                .unwrap();
+            if current[from].1 {
+                continue;
+            }
+            current.remove(from);
+
+            let to = current
+                .iter()
+                .rposition(|&(other, settled)| settled && rank[&other] < rank[&page])
+                .map_or(0, |idx| idx + 1);
+            current.insert(to, (page, true));
+            moves.push(PageMove { page, from, to });
+        }
+
+        Ok(moves)
+    }
+
+    /// The fewest swaps of neighbouring pages that turn the update into an
+    /// order the rules allow, one per pair of pages that are the wrong way
+    /// round in the closest such order.
+    ///
+    /// The search for that order grows exponentially with the number of
+    /// pages the rules leave pairwise unordered, so it fails with
+    /// `TooManyUnordered` for more than `MAX_UNORDERED` of them.
+    pub fn minimum_swaps(&self, update: &[u32]) -> Result<Vec<Swap>, OrderError> {
+        let precedes = self.precedence(update)?;
+        let unordered = width(&precedes);
+        if unordered > MAX_UNORDERED {
+            return Err(OrderError::TooManyUnordered(unordered));
+        }
+        let rank = ranks(update, &closest_order(&precedes));
+        let mut current = update.to_vec();
+        let mut swaps = Vec::new();
+
+        for end in (1..current.len()).rev() {
+            for index in 0..end {
+                if rank[&current[index]] > rank[&current[index + 1]] {
+                    swaps.push(Swap {
+                        index,
+                        pages: (current[index], current[index + 1]),
+                    });
+                    current.swap(index, index + 1);
+                }
+            }
+        }
+
+        Ok(swaps)
+    }
+
+    /// `precedes[i][j]` is whether the rules between the pages of the update
+    /// put `update[i]` before `update[j]`, directly or through other pages
+    /// of the update.
+    fn precedence(&self, update: &[u32]) -> Result<Precedence, OrderError> {
+        let mut seen = HashSet::new();
+        if let Some(&page) = update.iter().find(|&&page| !seen.insert(page)) {
+            return Err(OrderError::DuplicatePage(page));
+        }
+
+        let mut precedes: Precedence = update
+            .iter()
+            .map(|&before| {
+                update
+                    .iter()
+                    .map(|&after| self.has_rule(before, after))
+                    .collect()
+            })
+            .collect();
+        for k in 0..update.len() {
+            let through = precedes[k].clone();
+            for row in precedes.iter_mut().filter(|row| row[k]) {
+                for (reaches, &next) in row.iter_mut().zip(&through) {
+                    *reaches |= next;
+                }
+            }
+        }
+
+        let cyclic: Vec<u32> = (0..update.len())
+            .filter(|&i| precedes[i][i])
+            .map(|i| update[i])
+            .collect();
+        if !cyclic.is_empty() {
+            return Err(OrderError::Cycle(self.find_cycle(&cyclic)));
+        }
+
+        Ok(precedes)
+    }
+}
+
+/// Transitive "comes before" relation between the indices of an update.
+type Precedence = Vec<Vec<bool>>;
+
+/// Most pairwise unordered pages `minimum_swaps` searches through.
+pub const MAX_UNORDERED: usize = 10;
+
+/// Position of each page of the update in `order`, given as indices into
+/// the update.
+fn ranks(update: &[u32], order: &[usize]) -> HashMap<u32, usize> {
+    order
+        .iter()
+        .enumerate()
+        .map(|(rank, &idx)| (update[idx], rank))
+        .collect()
+}
+
+/// Marks one largest set of indices whose pages the rules allow to keep
+/// their current relative order.
+///
+/// Two pages conflict if the later one must come before the earlier one.
+/// Conflicts form a partial order, so a largest conflict-free set is a
+/// largest antichain, found from a maximum matching by Dilworth's and
+/// König's theorems.
+fn largest_consistent_subset(precedes: &Precedence) -> Vec<bool> {
+    let n = precedes.len();
+    let conflicts = |i: usize, j: usize| i < j && precedes[j][i];
+
+    // match_of[j] is the index matched to j on the right-hand side.
+    let mut match_of: Vec<Option<usize>> = vec![None; n];
+    let matched: Vec<bool> = (0..n)
+        .map(|i| augment(i, &conflicts, &mut match_of, &mut vec![false; n]))
+        .collect();
+
+    // Alternating paths from the unmatched left-hand indices.
+    let mut left = vec![false; n];
+    let mut right = vec![false; n];
+    let mut queue: Vec<usize> = (0..n).filter(|&i| !matched[i]).collect();
+    for &i in &queue {
+        left[i] = true;
+    }
+    while let Some(i) = queue.pop() {
+        for j in 0..n {
+            if !conflicts(i, j) || right[j] {
+                continue;
+            }
+            right[j] = true;
+            if let Some(k) = match_of[j] {
+                if !left[k] {
+                    left[k] = true;
+                    queue.push(k);
+                }
+            }
+        }
+    }
+
+    (0..n).map(|i| left[i] && !right[i]).collect()
+}
+
+/// Size of a largest set of pairwise unordered indices, by Dilworth's
+/// theorem the number of indices less a maximum matching of the order.
+fn width(precedes: &Precedence) -> usize {
+    let n = precedes.len();
+    let before = |i: usize, j: usize| precedes[i][j];
+    let mut match_of: Vec<Option<usize>> = vec![None; n];
+    let matched = (0..n)
+        .filter(|&i| augment(i, &before, &mut match_of, &mut vec![false; n]))
+        .count();
+    n - matched
+}
+
+/// Kuhn's augmenting path search for the left-hand index `i`.
+fn augment(
+    i: usize,
+    conflicts: &impl Fn(usize, usize) -> bool,
+    match_of: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for j in 0..match_of.len() {
+        if !conflicts(i, j) || visited[j] {
+            continue;
+        }
+        visited[j] = true;
+        if match_of[j].is_none_or(|k| augment(k, conflicts, match_of, visited)) {
+            match_of[j] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+/// An order the rules allow that keeps the `settled` indices in their
+/// current relative order, as indices into the update. Among the pages that
+/// may come next, the one earliest in the update is taken.
+fn target_order(precedes: &Precedence, settled: &[bool]) -> Vec<usize> {
+    let n = precedes.len();
+    let mut placed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+
+    while order.len() < n {
+        let next = (0..n)
+            .find(|&i| {
+                !placed[i]
+                    && (0..n).all(|j| placed[j] || !precedes[j][i])
+                    && (!settled[i] || (0..i).all(|j| placed[j] || !settled[j]))
+            })
+            .unwrap();
+        placed[next] = true;
+        order.push(next);
+    }
+
+    order
+}
+
+/// The order the rules allow with the fewest pairs of pages the wrong way
+/// round compared to the update, as indices into the update.
+///
+/// Searches over the sets of pages that can be placed first. Placing a page
+/// puts it after every page still to be placed that came earlier in the
+/// update. There are few such sets as long as the rules decide the order of
+/// most pairs of pages, see `MAX_UNORDERED`.
+fn closest_order(precedes: &Precedence) -> Vec<usize> {
+    let n = precedes.len();
+    let mut layer: BTreeMap<Vec<bool>, (usize, Vec<usize>)> =
+        BTreeMap::from([(vec![false; n], (0, Vec::new()))]);
+
+    for _ in 0..n {
+        let mut next_layer: BTreeMap<Vec<bool>, (usize, Vec<usize>)> = BTreeMap::new();
+        for (placed, (cost, order)) in layer {
+            for i in 0..n {
+                if placed[i] || (0..n).any(|j| !placed[j] && precedes[j][i]) {
+                    continue;
+                }
+                let cost = cost + (0..i).filter(|&j| !placed[j]).count();
+                let mut placed = placed.clone();
+                placed[i] = true;
+
+                if next_layer.get(&placed).is_none_or(|(best, _)| cost < *best) {
+                    let mut order = order.clone();
+                    order.push(i);
+                    next_layer.insert(placed, (cost, order));
+                }
+            }
+        }
+        layer = next_layer;
+    }
+
+    layer
+        .into_values()
+        .next()
+        .map_or(Vec::new(), |(_, order)| order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_moves(update: &[u32], moves: &[PageMove]) -> Vec<u32> {
+        let mut current = update.to_vec();
+        for page_move in moves {
+            let page = current.remove(page_move.from);
+            current.insert(page_move.to, page);
+        }
+        current
+    }
+
+    #[test]
+    fn check_minimum_moves() {
+        let rules = Rules::build("1|2\n1|3\n1|4\n1|5\n2|3\n2|4\n2|5\n3|4\n3|5\n4|5").unwrap();
+
+        let update = [2, 3, 4, 5, 1];
+        let moves = rules.minimum_moves(&update).unwrap();
+        assert_eq!(
+            moves,
+            vec![PageMove {
+                page: 1,
+                from: 4,
+                to: 0
+            }]
+        );
+
+        let update = [5, 2, 1, 4, 3];
+        let moves = rules.minimum_moves(&update).unwrap();
+        assert_eq!(moves.len(), 3);
+        assert_eq!(apply_moves(&update, &moves), vec![1, 2, 3, 4, 5]);
+
+        assert!(rules.minimum_moves(&[1, 2, 3]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_minimum_swaps() {
+        let rules = Rules::build("1|2\n1|3\n2|3").unwrap();
+        let swaps = rules.minimum_swaps(&[3, 1, 2]).unwrap();
+
+        assert_eq!(
+            swaps,
+            vec![
+                Swap {
+                    index: 0,
+                    pages: (3, 1)
+                },
+                Swap {
+                    index: 1,
+                    pages: (3, 2)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn check_partially_ordered_update() {
+        // The rules leave 1 and 2 unordered, so there is no single repair.
+        let rules = Rules::build("1|3\n2|3").unwrap();
+        let update = [3, 2, 1];
+        assert!(rules.repair(&update).is_err());
+
+        let moves = rules.minimum_moves(&update).unwrap();
+        assert_eq!(moves.len(), 1);
+        assert!(rules.is_valid(&apply_moves(&update, &moves)));
+        assert_eq!(rules.minimum_swaps(&update).unwrap().len(), 2);
+
+        // Keeping 1 and 2 in place needs a single move of 3, but two swaps.
+        let rules = Rules::build("3|1").unwrap();
+        let update = [1, 2, 3];
+        let moves = rules.minimum_moves(&update).unwrap();
+        assert_eq!(moves.len(), 1);
+        assert!(rules.is_valid(&apply_moves(&update, &moves)));
+        assert_eq!(rules.minimum_swaps(&update).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn check_too_many_unordered() {
+        let rules = Rules::build("100|101").unwrap();
+        let mut update = vec![101, 100];
+        update.extend(1..=10);
+
+        assert_eq!(rules.minimum_moves(&update).unwrap().len(), 1);
+        assert_eq!(
+            rules.minimum_swaps(&update),
+            Err(OrderError::TooManyUnordered(11))
+        );
+        assert_eq!(rules.minimum_swaps(&update[..11]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn check_moves_report_problems() {
+        let rules = Rules::build("1|2\n2|1").unwrap();
+        assert!(matches!(
+            rules.minimum_moves(&[1, 2]),
+            Err(OrderError::Cycle(_))
+        ));
+        assert_eq!(
+            rules.minimum_swaps(&[1, 3, 1]),
+            Err(OrderError::DuplicatePage(1))
+        );
+    }
+}
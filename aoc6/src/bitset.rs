@@ -0,0 +1,83 @@
+/// A fixed-size set of cell indices (or cell and direction states), one bit
+/// per value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(size: usize) -> BitSet {
+        BitSet {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        self.words[value / 64] |= 1 << (value % 64);
+    }
+
+    pub fn remove(&mut self, value: usize) {
+        self.words[value / 64] &= !(1 << (value % 64));
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.words[value / 64] & (1 << (value % 64)) != 0
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                Some(idx * 64 + bit)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_bitset() {
+        let mut set = BitSet::new(200);
+        set.insert(3);
+        set.insert(64);
+        set.insert(199);
+
+        assert!(set.contains(3) && set.contains(64) && set.contains(199));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 64, 199]);
+
+        set.remove(64);
+        let mut other = BitSet::new(200);
+        other.insert(5);
+        set.union_with(&other);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 5, 199]);
+
+        set.clear();
+        assert_eq!(set.len(), 0);
+    }
+}
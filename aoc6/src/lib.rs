@@ -0,0 +1,974 @@
+mod bitset;
+mod placement;
+mod record;
+mod report;
+mod walk;
+
+use bitset::BitSet;
+pub use placement::{Goal, Placement, Search};
+pub use record::{Capture, RecordFormat, Recording};
+pub use report::{to_csv, to_json, CandidateReport, ReportFormat, Trap};
+use std::error::Error;
+use std::fs;
+use std::thread;
+pub use walk::{Outcome, Walk};
+
+/// Marker in the jump tables for "no special tile ahead, the guard leaves".
+const NONE: u32 = u32::MAX;
+
+/// Upper bound on the frames of a recording, for guards that never leave.
+const MAX_FRAMES: usize = 10_000;
+
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    let input = fs::read_to_string(&config.file_path)?;
+    let mut lab = Lab::build(&input)?;
+
+    if config.turns.len() > lab.guards.len() {
+        return Err(format!(
+            "Got {} turning behaviours for {} guards.",
+            config.turns.len(),
+            lab.guards.len()
+        )
+        .into());
+    }
+    for (guard, &turn) in lab.guards.iter_mut().zip(&config.turns) {
+        guard.turn = turn;
+    }
+    lab.wrap = config.wrap;
+
+    println!("Number of distinct positions: {}", lab.distinct_positions());
+    println!("Total number of obstructions: {}", lab.loop_obstructions());
+
+    if let Some(format) = config.loops {
+        let reports = lab.loop_report();
+        match format {
+            ReportFormat::Csv => print!("{}", to_csv(&reports)),
+            ReportFormat::Json => print!("{}", to_json(&reports)),
+            ReportFormat::Map => print!("{}", lab.render_loop_obstructions(&reports)),
+        }
+    }
+
+    if let Some(k) = config.place {
+        let search = match config.beam {
+            Some(width) => Search::Beam { width },
+            None => lab.default_search(k),
+        };
+        let placement = lab.place_obstructions(k, config.goal, search);
+        let cells: Vec<String> = placement
+            .obstructions
+            .iter()
+            .map(|Position(i, j)| format!("({}, {})", i, j))
+            .collect();
+        println!(
+            "Best placement of up to {} obstructions ({:?} search): [{}] covering {} positions",
+            k,
+            search,
+            cells.join(", "),
+            placement.coverage
+        );
+    }
+
+    if let Some(format) = config.record {
+        let recording = lab.record(config.capture, MAX_FRAMES);
+        let output = config
+            .output
+            .unwrap_or_else(|| format!("patrol.{}", format.extension()));
+        match format {
+            RecordFormat::Ascii => fs::write(&output, recording.to_ascii())?,
+            RecordFormat::Asciicast => fs::write(&output, recording.to_asciicast(0.1))?,
+            RecordFormat::Gif => fs::write(&output, recording.to_gif(4, 10)?)?,
+        }
+        println!("Recorded {} frames to {}", recording.frames.len(), output);
+    }
+
+    Ok(())
+}
+
+/// The lab map. Tiles are kept as the raw input bytes:
+///
+/// - `.` floor and `#` obstacle,
+/// - `^`, `>`, `v` and `<` a guard starting on floor, facing that way,
+/// - `N`, `E`, `S` and `W` one-way tiles, which can only be entered while
+///   heading north (up), east (right), south (down) or west (left) and block
+///   the guard otherwise,
+/// - `/` and `\` turn tiles, which deflect a guard stepping onto them like a
+///   mirror,
+/// - lowercase letters other than `v` portals, which come in pairs: a guard
+///   stepping onto one is moved to the other and carries on in the same
+///   direction.
+///
+/// With `wrap` set the map is a torus: walking off one edge re-enters from
+/// the opposite edge, so guards never leave.
+pub struct Lab {
+    pub m: usize,
+    pub n: usize,
+    pub guards: Vec<Guard>,
+    pub wrap: bool,
+    tiles: Vec<u8>,
+    /// For every portal cell the cell of its partner, `NONE` elsewhere.
+    portals: Vec<u32>,
+    /// For every direction and cell, the index of the next tile strictly
+    /// ahead of the cell that is not plain floor, or `NONE` if the guard
+    /// would walk off the map first.
+    jumps: [Vec<u32>; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position(pub usize, pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guard {
+    pub position: Position,
+    pub facing: Direction,
+    pub turn: Turn,
+}
+
+/// How a guard changes direction when blocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Right,
+    Left,
+    Reverse,
+}
+
+impl Turn {
+    pub fn parse(turn: &str) -> Result<Turn, &'static str> {
+        match turn.to_lowercase().as_str() {
+            "right" => Ok(Turn::Right),
+            "left" => Ok(Turn::Left),
+            "reverse" => Ok(Turn::Reverse),
+            _ => Err("Unknown turning behaviour. Allowed values: ['right', 'left', 'reverse']"),
+        }
+    }
+
+    pub fn apply(self, direction: Direction) -> Direction {
+        match self {
+            Turn::Right => direction.turn_right(),
+            Turn::Left => direction.turn_left(),
+            Turn::Reverse => direction.reverse(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Floor,
+    Obstacle,
+    OneWay(Direction),
+    Mirror(Mirror),
+    Portal,
+}
+
+/// `/` and `\` respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mirror {
+    Slash,
+    Backslash,
+}
+
+impl Mirror {
+    fn reflect(self, direction: Direction) -> Direction {
+        match (self, direction) {
+            (Mirror::Slash, Direction::Up) | (Mirror::Backslash, Direction::Down) => {
+                Direction::Right
+            }
+            (Mirror::Slash, Direction::Right) | (Mirror::Backslash, Direction::Left) => {
+                Direction::Up
+            }
+            (Mirror::Slash, Direction::Down) | (Mirror::Backslash, Direction::Up) => {
+                Direction::Left
+            }
+            (Mirror::Slash, Direction::Left) | (Mirror::Backslash, Direction::Right) => {
+                Direction::Down
+            }
+        }
+    }
+}
+
+fn classify(tile: u8) -> Option<Tile> {
+    match tile {
+        b'.' | b'^' | b'>' | b'v' | b'<' => Some(Tile::Floor),
+        b'#' => Some(Tile::Obstacle),
+        b'N' => Some(Tile::OneWay(Direction::Up)),
+        b'E' => Some(Tile::OneWay(Direction::Right)),
+        b'S' => Some(Tile::OneWay(Direction::Down)),
+        b'W' => Some(Tile::OneWay(Direction::Left)),
+        b'/' => Some(Tile::Mirror(Mirror::Slash)),
+        b'\\' => Some(Tile::Mirror(Mirror::Backslash)),
+        b'a'..=b'z' => Some(Tile::Portal),
+        _ => None,
+    }
+}
+
+/// The result of a guard trying to step onto a tile: it stays where it is,
+/// or ends up on the given cell facing the given direction.
+enum Entry {
+    Blocked,
+    Pass(usize, Direction),
+}
+
+impl Lab {
+    pub fn build(input: &str) -> Result<Lab, &'static str> {
+        let tiles: Vec<u8> = input.lines().flat_map(|row| row.bytes()).collect();
+        let m = input.lines().count();
+        let mut iter = input.lines().map(|row| row.len());
+        let n = match iter.next() {
+            Some(x) => {
+                if iter.all(|elem| elem == x) {
+                    x
+                } else {
+                    return Err("Invalid input! Failed to construct map.");
+                }
+            }
+            None => 0,
+        };
+        if tiles.iter().any(|&tile| classify(tile).is_none()) {
+            return Err("Invalid input! Unknown tile on the map.");
+        }
+
+        let guards: Vec<Guard> = tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, &tile)| {
+                let facing = Direction::from_guard(tile)?;
+                Some(Guard {
+                    position: Position(idx / n, idx % n),
+                    facing,
+                    turn: Turn::Right,
+                })
+            })
+            .collect();
+        if guards.is_empty() {
+            return Err("Invalid input! No guard on the map.");
+        }
+
+        let mut portals = vec![NONE; tiles.len()];
+        for letter in (b'a'..=b'z').filter(|&letter| letter != b'v') {
+            let cells: Vec<usize> = (0..tiles.len())
+                .filter(|&idx| tiles[idx] == letter)
+                .collect();
+            match cells[..] {
+                [] => {}
+                [first, second] => {
+                    portals[first] = second as u32;
+                    portals[second] = first as u32;
+                }
+                _ => return Err("Invalid input! Portals must come in pairs."),
+            }
+        }
+
+        let mut lab = Lab {
+            m,
+            n,
+            guards,
+            wrap: false,
+            tiles,
+            portals,
+            jumps: Default::default(),
+        };
+        lab.jumps = Direction::ALL.map(|direction| lab.jump_table(direction));
+
+        Ok(lab)
+    }
+
+    fn tile(&self, idx: usize) -> Tile {
+        classify(self.tiles[idx]).unwrap()
+    }
+
+    /// Builds the jump table for one direction by sweeping each row or
+    /// column against the direction of travel.
+    fn jump_table(&self, direction: Direction) -> Vec<u32> {
+        let mut jumps = vec![NONE; self.tiles.len()];
+
+        for idx in self.sweep_order(direction) {
+            let next = self.step(idx, direction);
+            jumps[idx] = match next {
+                Some(next) if self.tile(next) != Tile::Floor => next as u32,
+                Some(next) => jumps[next],
+                None => NONE,
+            };
+        }
+
+        jumps
+    }
+
+    /// Cell indices ordered so that the cell ahead in `direction` always
+    /// comes before the cell behind it.
+    fn sweep_order(&self, direction: Direction) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.tiles.len()).collect();
+        match direction {
+            Direction::Up | Direction::Left => {}
+            Direction::Down | Direction::Right => order.reverse(),
+        }
+        order
+    }
+
+    fn index(&self, position: Position) -> usize {
+        position.0 * self.n + position.1
+    }
+
+    fn position(&self, idx: usize) -> Position {
+        Position(idx / self.n, idx % self.n)
+    }
+
+    fn step(&self, idx: usize, direction: Direction) -> Option<usize> {
+        let (i, j) = (idx / self.n, idx % self.n);
+        match direction {
+            Direction::Up => (i > 0).then(|| idx - self.n),
+            Direction::Down => (i + 1 < self.m).then(|| idx + self.n),
+            Direction::Left => (j > 0).then(|| idx - 1),
+            Direction::Right => (j + 1 < self.n).then(|| idx + 1),
+        }
+    }
+
+    /// The next cell, re-entering from the opposite edge in wrap mode.
+    fn step_or_wrap(&self, idx: usize, direction: Direction) -> Option<usize> {
+        match self.step(idx, direction) {
+            Some(next) => Some(next),
+            None if self.wrap => Some(self.wrap_around(idx, direction)),
+            None => None,
+        }
+    }
+
+    /// The cell on the opposite edge from `idx`, in the same row or column.
+    fn wrap_around(&self, idx: usize, direction: Direction) -> usize {
+        let (i, j) = (idx / self.n, idx % self.n);
+        match direction {
+            Direction::Up => (self.m - 1) * self.n + j,
+            Direction::Down => j,
+            Direction::Left => i * self.n + self.n - 1,
+            Direction::Right => i * self.n,
+        }
+    }
+
+    /// Number of steps from `idx` to the edge of the map.
+    fn distance_to_edge(&self, idx: usize, direction: Direction) -> usize {
+        let (i, j) = (idx / self.n, idx % self.n);
+        match direction {
+            Direction::Up => i,
+            Direction::Down => self.m - 1 - i,
+            Direction::Left => j,
+            Direction::Right => self.n - 1 - j,
+        }
+    }
+
+    /// The cell `distance` steps ahead, which must lie on the map.
+    fn advance(&self, idx: usize, direction: Direction, distance: usize) -> usize {
+        match direction {
+            Direction::Up => idx - distance * self.n,
+            Direction::Down => idx + distance * self.n,
+            Direction::Left => idx - distance,
+            Direction::Right => idx + distance,
+        }
+    }
+
+    /// Number of steps from `from` to `to` if `to` lies straight ahead in
+    /// `direction`.
+    fn distance_ahead(&self, from: usize, to: usize, direction: Direction) -> Option<usize> {
+        let (fi, fj) = (from / self.n, from % self.n);
+        let (ti, tj) = (to / self.n, to % self.n);
+        match direction {
+            Direction::Up => (fj == tj && ti < fi).then(|| fi - ti),
+            Direction::Down => (fj == tj && ti > fi).then(|| ti - fi),
+            Direction::Left => (fi == ti && tj < fj).then(|| fj - tj),
+            Direction::Right => (fi == ti && tj > fj).then(|| tj - fj),
+        }
+    }
+
+    fn enter(&self, idx: usize, direction: Direction, obstructions: &[usize]) -> Entry {
+        if obstructions.contains(&idx) {
+            return Entry::Blocked;
+        }
+        match self.tile(idx) {
+            Tile::Floor => Entry::Pass(idx, direction),
+            Tile::Obstacle => Entry::Blocked,
+            Tile::OneWay(allowed) if allowed == direction => Entry::Pass(idx, direction),
+            Tile::OneWay(_) => Entry::Blocked,
+            Tile::Mirror(mirror) => Entry::Pass(idx, mirror.reflect(direction)),
+            Tile::Portal => Entry::Pass(self.portals[idx] as usize, direction),
+        }
+    }
+
+    /// One step of the guard: either a move onto the next tile or a turn on
+    /// the spot. `None` once the guard walks off the map. A guard stepping
+    /// onto a portal lands on its partner straight away.
+    fn tick(
+        &self,
+        position: usize,
+        direction: Direction,
+        turn: Turn,
+        obstructions: &[usize],
+    ) -> Option<(usize, Direction)> {
+        let next = self.step_or_wrap(position, direction)?;
+        match self.enter(next, direction, obstructions) {
+            Entry::Blocked => Some((position, turn.apply(direction))),
+            Entry::Pass(cell, direction) => Some((cell, direction)),
+        }
+    }
+
+    /// Walks the guard step by step until it leaves the map or repeats a
+    /// state. Returns the visited cells and whether the guard is trapped.
+    fn patrol(&self, guard: &Guard, obstructions: &[usize]) -> (BitSet, bool) {
+        let mut visited = BitSet::new(self.tiles.len());
+        let mut seen = BitSet::new(4 * self.tiles.len());
+        let mut position = self.index(guard.position);
+        let mut direction = guard.facing;
+        visited.insert(position);
+
+        loop {
+            let state = direction as usize * self.tiles.len() + position;
+            if seen.contains(state) {
+                return (visited, true);
+            }
+            seen.insert(state);
+
+            match self.tick(position, direction, guard.turn, obstructions) {
+                Some((next, facing)) => {
+                    position = next;
+                    direction = facing;
+                    visited.insert(position);
+                }
+                None => return (visited, false),
+            }
+        }
+    }
+
+    /// Cells visited by at least one guard.
+    fn covered(&self) -> BitSet {
+        let mut covered = BitSet::new(self.tiles.len());
+        for guard in &self.guards {
+            covered.union_with(&self.patrol(guard, &[]).0);
+        }
+        covered
+    }
+
+    pub fn distinct_positions(&self) -> usize {
+        self.covered().len()
+    }
+
+    /// Indices of the guards that never leave the map.
+    pub fn trapped_guards(&self) -> Vec<usize> {
+        (0..self.guards.len())
+            .filter(|&idx| self.patrol(&self.guards[idx], &[]).1)
+            .collect()
+    }
+
+    /// Whether the guard is trapped once `obstructions` are added. The guard
+    /// moves from one special tile to the next using the jump tables, and only
+    /// the states where it turns or enters a special tile are recorded;
+    /// `seen` must hold `8 * m * n` states.
+    fn loops_with(&self, guard: &Guard, obstructions: &[usize], seen: &mut BitSet) -> bool {
+        seen.clear();
+        let mut position = self.index(guard.position);
+        let mut direction = guard.facing;
+
+        loop {
+            let jump = self.jumps[direction as usize][position];
+            let special = match jump {
+                NONE => None,
+                jump => self
+                    .distance_ahead(position, jump as usize, direction)
+                    .map(|distance| (distance, jump as usize)),
+            };
+            let extra = obstructions
+                .iter()
+                .filter_map(|&cell| self.distance_ahead(position, cell, direction))
+                .min();
+
+            // In wrap mode the cell beyond the edge is the first one on the
+            // opposite edge, which may be obstructed itself.
+            let (distance, entry) = match (special, extra) {
+                (Some((special, _)), Some(extra)) if extra < special => (extra, Entry::Blocked),
+                (None, Some(extra)) => (extra, Entry::Blocked),
+                (Some((special, cell)), _) => (special, self.enter(cell, direction, &[])),
+                (None, None) if self.wrap => {
+                    let distance = self.distance_to_edge(position, direction) + 1;
+                    let cell = self.wrap_around(position, direction);
+                    (distance, self.enter(cell, direction, obstructions))
+                }
+                (None, None) => return false,
+            };
+
+            // Turning in front of a tile and entering a tile are recorded
+            // separately, since a guard can be blocked right after entering.
+            let (kind, state_direction) = match entry {
+                Entry::Blocked => {
+                    position = self.advance(position, direction, distance - 1);
+                    let blocked = direction;
+                    direction = guard.turn.apply(direction);
+                    (0, blocked)
+                }
+                Entry::Pass(cell, facing) => {
+                    position = cell;
+                    direction = facing;
+                    (1, facing)
+                }
+            };
+
+            let state = (kind * 4 + state_direction as usize) * self.tiles.len() + position;
+            if seen.contains(state) {
+                return true;
+            }
+            seen.insert(state);
+        }
+    }
+
+    /// Indices of the guards that leave the map, and the plain floor cells
+    /// on their patrols: elsewhere a new obstruction cannot change their
+    /// course.
+    fn obstruction_candidates(&self) -> (Vec<usize>, Vec<usize>) {
+        let trapped = self.trapped_guards();
+        let leaving: Vec<usize> = (0..self.guards.len())
+            .filter(|idx| !trapped.contains(idx))
+            .collect();
+
+        let mut candidates = BitSet::new(self.tiles.len());
+        for &idx in &leaving {
+            candidates.union_with(&self.patrol(&self.guards[idx], &[]).0);
+        }
+        for guard in &self.guards {
+            candidates.remove(self.index(guard.position));
+        }
+        let candidates = candidates
+            .iter()
+            .filter(|&idx| self.tile(idx) == Tile::Floor)
+            .collect();
+
+        (leaving, candidates)
+    }
+
+    /// Number of cells where a single new obstruction traps at least one
+    /// guard that would otherwise leave. Always zero in wrap mode, where no
+    /// guard ever leaves.
+    pub fn loop_obstructions(&self) -> usize {
+        let (leaving, candidates) = self.obstruction_candidates();
+
+        parallel_map(
+            &candidates,
+            || BitSet::new(8 * self.tiles.len()),
+            |&obstruction, seen| {
+                leaving
+                    .iter()
+                    .any(|&idx| self.loops_with(&self.guards[idx], &[obstruction], seen))
+            },
+        )
+        .into_iter()
+        .filter(|&loops| loops)
+        .count()
+    }
+}
+
+/// Applies `f` to every item, in order, with the items split evenly across
+/// the available threads. Each thread gets its own scratch space from
+/// `scratch`.
+fn parallel_map<I: Sync, T: Send, S>(
+    items: &[I],
+    scratch: impl Fn() -> S + Sync,
+    f: impl Fn(&I, &mut S) -> T + Sync,
+) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, |count| count.get());
+    let chunk_size = items.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let (scratch, f) = (&scratch, &f);
+                scope.spawn(move || {
+                    let mut space = scratch();
+                    chunk
+                        .iter()
+                        .map(|item| f(item, &mut space))
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    fn glyph(self) -> u8 {
+        match self {
+            Direction::Up => b'^',
+            Direction::Right => b'>',
+            Direction::Down => b'v',
+            Direction::Left => b'<',
+        }
+    }
+
+    fn from_guard(tile: u8) -> Option<Direction> {
+        match tile {
+            b'^' => Some(Direction::Up),
+            b'>' => Some(Direction::Right),
+            b'v' => Some(Direction::Down),
+            b'<' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Direction {
+        Direction::ALL[(self as usize + 2) % 4]
+    }
+}
+
+pub struct Config {
+    pub file_path: String,
+    /// Turning behaviour of the guards in reading order; guards without an
+    /// entry turn right.
+    pub turns: Vec<Turn>,
+    /// Treat the map as a torus.
+    pub wrap: bool,
+    /// Print a report of every candidate obstruction in this format.
+    pub loops: Option<ReportFormat>,
+    pub record: Option<RecordFormat>,
+    pub capture: Capture,
+    /// Where to write the recording; `patrol.<extension>` by default.
+    pub output: Option<String>,
+    /// Search for the best placement of up to this many new obstructions.
+    pub place: Option<usize>,
+    pub goal: Goal,
+    /// Beam width to use instead of the default search.
+    pub beam: Option<usize>,
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+        args.next();
+
+        let mut file_path = "data.txt".to_string();
+        let mut turns = Vec::new();
+        let mut wrap = false;
+        let mut loops = None;
+        let mut record = None;
+        let mut capture = Capture::Step;
+        let mut output = None;
+        let mut place = None;
+        let mut goal = Goal::Maximise;
+        let mut beam = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--turn" => match args.next() {
+                    Some(list) => {
+                        turns = list
+                            .split(',')
+                            .map(Turn::parse)
+                            .collect::<Result<Vec<Turn>, _>>()?
+                    }
+                    None => return Err("Did not get a list of turning behaviours!"),
+                },
+                "--wrap" => wrap = true,
+                "--loops" => match args.next() {
+                    Some(format) => loops = Some(ReportFormat::parse(&format)?),
+                    None => return Err("Did not get a report format!"),
+                },
+                "--record" => match args.next() {
+                    Some(format) => record = Some(RecordFormat::parse(&format)?),
+                    None => return Err("Did not get a recording format!"),
+                },
+                "--every" => match args.next() {
+                    Some(mode) => capture = Capture::parse(&mode)?,
+                    None => return Err("Did not get a capture mode!"),
+                },
+                "--output" => match args.next() {
+                    Some(path) => output = Some(path),
+                    None => return Err("Did not get an output path!"),
+                },
+                "--place" => match args.next().map(|k| k.parse()) {
+                    Some(Ok(k)) => place = Some(k),
+                    _ => return Err("Did not get a number of obstructions to place!"),
+                },
+                "--goal" => match args.next() {
+                    Some(target) => goal = Goal::parse(&target)?,
+                    None => return Err("Did not get a goal!"),
+                },
+                "--beam" => match args.next().map(|width| width.parse()) {
+                    Some(Ok(width)) if width > 0 => beam = Some(width),
+                    _ => return Err("Did not get a positive beam width!"),
+                },
+                _ => file_path = arg,
+            }
+        }
+
+        Ok(Config {
+            file_path,
+            turns,
+            wrap,
+            loops,
+            record,
+            capture,
+            output,
+            place,
+            goal,
+            beam,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from the puzzle, shared by the tests of every module.
+    pub(crate) const EXAMPLE: &str = "\
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...";
+
+    /// The example reflected left to right.
+    const MIRRORED: &str = "\
+.....#....
+#.........
+..........
+.......#..
+..#.......
+..........
+.....^..#.
+.#........
+.........#
+...#......";
+
+    /// Fast loop check against the step by step patrol for every floor cell.
+    fn assert_consistent(lab: &Lab) {
+        let mut seen = BitSet::new(8 * lab.tiles.len());
+        for guard in &lab.guards {
+            for cell in 0..lab.tiles.len() {
+                if lab.tiles[cell] != b'.' {
+                    continue;
+                }
+                assert_eq!(
+                    lab.loops_with(guard, &[cell], &mut seen),
+                    lab.patrol(guard, &[cell]).1,
+                    "obstruction at {}",
+                    cell
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_distinct_positions() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        assert_eq!(lab.distinct_positions(), 41);
+    }
+
+    #[test]
+    fn check_loop_obstructions() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        assert_eq!(lab.loop_obstructions(), 6);
+        assert_consistent(&lab);
+    }
+
+    #[test]
+    fn check_jump_table() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        let start = lab.index(lab.guards[0].position);
+
+        assert_eq!(lab.jumps[Direction::Up as usize][start], 4);
+        assert_eq!(lab.jumps[Direction::Left as usize][start], 61);
+        assert_eq!(lab.jumps[Direction::Right as usize][start], NONE);
+        assert_eq!(lab.jumps[Direction::Right as usize][44], 47);
+        assert_eq!(lab.jumps[Direction::Down as usize][4], NONE);
+        assert_eq!(lab.jumps[Direction::Up as usize][14], 4);
+    }
+
+    #[test]
+    fn check_left_turning_guard() {
+        let mut lab = Lab::build(MIRRORED).unwrap();
+        lab.guards[0].turn = Turn::Left;
+
+        assert_eq!(lab.distinct_positions(), 41);
+        assert_eq!(lab.loop_obstructions(), 6);
+        assert_consistent(&lab);
+    }
+
+    #[test]
+    fn check_reversing_guard() {
+        let mut lab = Lab::build("#\n.\n^\n.\n#").unwrap();
+        lab.guards[0].turn = Turn::Reverse;
+
+        assert_eq!(lab.trapped_guards(), vec![0]);
+        assert_eq!(lab.distinct_positions(), 3);
+        assert_eq!(lab.loop_obstructions(), 0);
+    }
+
+    #[test]
+    fn check_multiple_guards() {
+        let lab = Lab::build("....#\n>....\n.....\n....<").unwrap();
+
+        assert_eq!(lab.guards.len(), 2);
+        assert_eq!(lab.guards[0].facing, Direction::Right);
+        assert_eq!(lab.guards[1].position, Position(3, 4));
+        // The first guard walks the second row, the second guard the last.
+        assert_eq!(lab.distinct_positions(), 10);
+        assert!(lab.trapped_guards().is_empty());
+    }
+
+    #[test]
+    fn check_special_tiles() {
+        // The guard is deflected up by the mirror and passes the one-way tile
+        // heading north, then walks off the top edge.
+        let lab = Lab::build("..N\n...\n>./").unwrap();
+        assert_eq!(lab.distinct_positions(), 5);
+
+        // Heading west through an east-only tile is blocked, so the guard
+        // turns right and walks up.
+        let lab = Lab::build("...\n.E<").unwrap();
+        assert_eq!(lab.distinct_positions(), 2);
+
+        let lab = Lab::build(
+            "\
+.#..\\.
+......
+.>../.
+.N..S.
+......",
+        )
+        .unwrap();
+        assert_consistent(&lab);
+    }
+
+    #[test]
+    fn check_wrap() {
+        let mut lab = Lab::build(EXAMPLE).unwrap();
+        lab.wrap = true;
+
+        assert_eq!(lab.trapped_guards(), vec![0]);
+        assert_eq!(lab.loop_obstructions(), 0);
+        assert_consistent(&lab);
+
+        let mut lab = Lab::build(".#.\n...\n.^.").unwrap();
+        lab.wrap = true;
+        let walk = lab.walk(&lab.guards[0], &[]).unwrap();
+
+        assert_eq!(
+            walk.outcome,
+            Outcome::Loop {
+                entry_step: 2,
+                length: 3
+            }
+        );
+        assert_eq!(
+            walk.tiles,
+            vec![
+                Position(2, 1),
+                Position(1, 1),
+                Position(1, 2),
+                Position(1, 0)
+            ]
+        );
+
+        // Re-entering onto an obstacle blocks the guard at the edge.
+        let mut lab = Lab::build("..\n^.\n#.").unwrap();
+        lab.wrap = true;
+        assert_eq!(lab.distinct_positions(), 3);
+        assert_consistent(&lab);
+    }
+
+    #[test]
+    fn check_portals() {
+        let lab = Lab::build("a..#\n.>.a").unwrap();
+        let walk = lab.walk(&lab.guards[0], &[]).unwrap();
+
+        assert_eq!(
+            walk.tiles,
+            vec![
+                Position(1, 1),
+                Position(1, 2),
+                Position(0, 0),
+                Position(0, 1),
+                Position(0, 2),
+            ]
+        );
+        assert_eq!(
+            walk.outcome,
+            Outcome::Exit {
+                position: Position(1, 2),
+                direction: Direction::Down,
+            }
+        );
+
+        let lab = Lab::build(
+            "\
+.#..b.
+a.....
+.>...a
+.N..S.
+b.....",
+        )
+        .unwrap();
+        assert_consistent(&lab);
+
+        assert!(Lab::build("a.^").is_err());
+        assert!(Lab::build("a.^a.a").is_err());
+    }
+
+    #[test]
+    fn check_config() {
+        let args = ["aoc6", "map.txt", "--turn", "left,reverse"].map(String::from);
+        let config = Config::build(args.into_iter()).unwrap();
+
+        assert_eq!(config.file_path, "map.txt");
+        assert_eq!(config.turns, vec![Turn::Left, Turn::Reverse]);
+        assert_eq!(config.loops, None);
+        assert_eq!(config.record, None);
+        assert!(!config.wrap);
+
+        let args = [
+            "aoc6", "--wrap", "--loops", "json", "--record", "gif", "--every", "turn",
+        ];
+        let config = Config::build(args.map(String::from).into_iter()).unwrap();
+        assert!(config.wrap);
+        assert_eq!(config.place, None);
+        assert_eq!(config.loops, Some(ReportFormat::Json));
+        assert_eq!(config.record, Some(RecordFormat::Gif));
+        assert_eq!(config.capture, Capture::Turn);
+
+        let args = ["aoc6", "--place", "3", "--goal", "min", "--beam", "8"];
+        let config = Config::build(args.map(String::from).into_iter()).unwrap();
+        assert_eq!(config.place, Some(3));
+        assert_eq!(config.goal, Goal::Minimise);
+        assert_eq!(config.beam, Some(8));
+
+        let args = ["aoc6", "--beam", "0"].map(String::from);
+        assert!(Config::build(args.into_iter()).is_err());
+
+        let args = ["aoc6", "--turn", "up"].map(String::from);
+        assert!(Config::build(args.into_iter()).is_err());
+    }
+}
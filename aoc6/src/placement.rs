@@ -0,0 +1,263 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::bitset::BitSet;
+use crate::{parallel_map, Lab, Position, Tile};
+
+/// Whether to look for the largest or the smallest patrol coverage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Maximise,
+    Minimise,
+}
+
+impl Goal {
+    pub fn parse(goal: &str) -> Result<Goal, &'static str> {
+        match goal.to_lowercase().as_str() {
+            "max" => Ok(Goal::Maximise),
+            "min" => Ok(Goal::Minimise),
+            _ => Err("Unknown goal. Allowed goals: ['max', 'min']"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+    /// Tries every placement that can make a difference.
+    Exhaustive,
+    /// Keeps only the `width` best placements of each size when adding the
+    /// next obstruction.
+    Beam { width: usize },
+}
+
+/// Placements tried before `default_search` switches to a beam search.
+const EXHAUSTIVE_BUDGET: f64 = 100_000.0;
+const BEAM_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    /// New obstructions, in reading order.
+    pub obstructions: Vec<Position>,
+    /// Distinct cells visited by the guards.
+    pub coverage: usize,
+}
+
+/// A set of obstructions and the patrols it results in.
+struct Node {
+    obstructions: Vec<usize>,
+    visited: BitSet,
+    /// Whether a guard that leaves the unchanged map is trapped.
+    trapped: bool,
+}
+
+impl Lab {
+    /// Exhaustive search when the number of placements to try looks
+    /// manageable, beam search otherwise.
+    pub fn default_search(&self, k: usize) -> Search {
+        let root = self.evaluate(Vec::new(), &[]);
+        let candidates = self.expansions(&root).count() as f64;
+        if candidates.powi(k as i32) <= EXHAUSTIVE_BUDGET {
+            Search::Exhaustive
+        } else {
+            Search::Beam { width: BEAM_WIDTH }
+        }
+    }
+
+    /// The placement of up to `k` new obstructions on plain floor that gives
+    /// the largest or smallest coverage, among placements that trap no guard
+    /// that would otherwise leave. Ties go to fewer obstructions.
+    ///
+    /// Obstructions are only added on cells the guards currently visit:
+    /// any other obstruction is never reached, so this still covers every
+    /// placement that makes a difference.
+    pub fn place_obstructions(&self, k: usize, goal: Goal, search: Search) -> Placement {
+        let leaving: Vec<usize> = self.obstruction_candidates().0;
+        let root = self.evaluate(Vec::new(), &leaving);
+
+        let mut best = (root.visited.len(), Vec::new());
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        let mut frontier = vec![root];
+
+        for _ in 0..k {
+            let children: Vec<Vec<usize>> = frontier
+                .iter()
+                .flat_map(|node| {
+                    self.expansions(node).map(move |cell| {
+                        let mut obstructions = node.obstructions.clone();
+                        obstructions.push(cell);
+                        obstructions.sort();
+                        obstructions
+                    })
+                })
+                .filter(|obstructions| seen.insert(obstructions.clone()))
+                .collect();
+
+            let mut nodes = parallel_map(
+                &children,
+                || (),
+                |obstructions, _| self.evaluate(obstructions.clone(), &leaving),
+            );
+
+            for node in nodes.iter().filter(|node| !node.trapped) {
+                let candidate = (node.visited.len(), node.obstructions.clone());
+                if compare(goal, &candidate, &best) == Ordering::Less {
+                    best = candidate;
+                }
+            }
+
+            if let Search::Beam { width } = search {
+                nodes.retain(|node| !node.trapped);
+                nodes.sort_by_cached_key(|node| rank(goal, node));
+                nodes.truncate(width);
+            }
+            if nodes.is_empty() {
+                break;
+            }
+            frontier = nodes;
+        }
+
+        Placement {
+            obstructions: best.1.iter().map(|&idx| self.position(idx)).collect(),
+            coverage: best.0,
+        }
+    }
+
+    /// Patrols of all guards with `obstructions` added.
+    fn evaluate(&self, obstructions: Vec<usize>, leaving: &[usize]) -> Node {
+        let mut visited = BitSet::new(self.tiles.len());
+        let mut trapped = false;
+        for (idx, guard) in self.guards.iter().enumerate() {
+            let (cells, loops) = self.patrol(guard, &obstructions);
+            visited.union_with(&cells);
+            trapped |= loops && leaving.contains(&idx);
+        }
+
+        Node {
+            obstructions,
+            visited,
+            trapped,
+        }
+    }
+
+    /// Cells where one more obstruction could change the patrols of `node`.
+    fn expansions<'a>(&'a self, node: &'a Node) -> impl Iterator<Item = usize> + 'a {
+        node.visited.iter().filter(move |&idx| {
+            self.tile(idx) == Tile::Floor
+                && !node.obstructions.contains(&idx)
+                && self
+                    .guards
+                    .iter()
+                    .all(|guard| self.index(guard.position) != idx)
+        })
+    }
+}
+
+/// Orders placements from best to worst: by coverage, then by the number of
+/// obstructions, then by the obstructions themselves.
+fn compare(goal: Goal, a: &(usize, Vec<usize>), b: &(usize, Vec<usize>)) -> Ordering {
+    let coverage = match goal {
+        Goal::Maximise => b.0.cmp(&a.0),
+        Goal::Minimise => a.0.cmp(&b.0),
+    };
+    coverage.then(a.1.len().cmp(&b.1.len())).then(a.1.cmp(&b.1))
+}
+
+fn rank(goal: Goal, node: &Node) -> (i64, Vec<usize>) {
+    let coverage = node.visited.len() as i64;
+    match goal {
+        Goal::Maximise => (-coverage, node.obstructions.clone()),
+        Goal::Minimise => (coverage, node.obstructions.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+    use crate::Outcome;
+
+    /// Coverage of the example's guard with `obstructions`, or `None` if it
+    /// gets trapped.
+    fn coverage(lab: &Lab, obstructions: &[Position]) -> Option<usize> {
+        let walk = lab.walk(&lab.guards[0], obstructions).unwrap();
+        match walk.outcome {
+            Outcome::Exit { .. } => Some(walk.tiles.len()),
+            Outcome::Loop { .. } => None,
+        }
+    }
+
+    /// Best coverage over every placement of up to two obstructions.
+    fn brute_force(lab: &Lab, goal: Goal) -> usize {
+        let floor: Vec<Position> = (0..lab.tiles.len())
+            .filter(|&idx| lab.tiles[idx] == b'.')
+            .map(|idx| lab.position(idx))
+            .collect();
+
+        let mut placements: Vec<Vec<Position>> = vec![Vec::new()];
+        for (i, &first) in floor.iter().enumerate() {
+            placements.push(vec![first]);
+            for &second in &floor[i + 1..] {
+                placements.push(vec![first, second]);
+            }
+        }
+
+        let coverages = placements
+            .iter()
+            .filter_map(|obstructions| coverage(lab, obstructions));
+        match goal {
+            Goal::Maximise => coverages.max().unwrap(),
+            Goal::Minimise => coverages.min().unwrap(),
+        }
+    }
+
+    #[test]
+    fn check_exhaustive() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+
+        for goal in [Goal::Maximise, Goal::Minimise] {
+            let placement = lab.place_obstructions(2, goal, Search::Exhaustive);
+            assert_eq!(placement.coverage, brute_force(&lab, goal));
+            assert_eq!(
+                coverage(&lab, &placement.obstructions),
+                Some(placement.coverage)
+            );
+        }
+
+        let placement = lab.place_obstructions(1, Goal::Minimise, Search::Exhaustive);
+        assert_eq!(placement.obstructions, vec![Position(5, 4)]);
+        assert_eq!(placement.coverage, 6);
+        assert_eq!(
+            lab.place_obstructions(0, Goal::Maximise, Search::Exhaustive),
+            Placement {
+                obstructions: Vec::new(),
+                coverage: 41
+            }
+        );
+    }
+
+    #[test]
+    fn check_beam() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        let exhaustive = lab.place_obstructions(3, Goal::Maximise, Search::Exhaustive);
+        assert_eq!(exhaustive.coverage, 48);
+
+        // No one or two obstructions improve on the example, so a narrow beam
+        // has nothing to go on; a wide one keeps every placement.
+        let wide = lab.place_obstructions(3, Goal::Maximise, Search::Beam { width: 10_000 });
+        assert_eq!(wide, exhaustive);
+
+        for goal in [Goal::Maximise, Goal::Minimise] {
+            let narrow = lab.place_obstructions(3, goal, Search::Beam { width: 4 });
+            assert!(narrow.obstructions.len() <= 3);
+            assert_eq!(coverage(&lab, &narrow.obstructions), Some(narrow.coverage));
+        }
+    }
+
+    #[test]
+    fn check_default_search() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+
+        assert_eq!(lab.default_search(2), Search::Exhaustive);
+        assert_eq!(lab.default_search(5), Search::Beam { width: BEAM_WIDTH });
+    }
+}
@@ -0,0 +1,408 @@
+use crate::bitset::BitSet;
+use crate::{Direction, Lab, Tile};
+
+/// When to capture a frame of the patrol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    /// After every step of the guards.
+    Step,
+    /// Only after steps in which at least one guard changed direction.
+    Turn,
+}
+
+impl Capture {
+    pub fn parse(capture: &str) -> Result<Capture, &'static str> {
+        match capture.to_lowercase().as_str() {
+            "step" => Ok(Capture::Step),
+            "turn" => Ok(Capture::Turn),
+            _ => Err("Unknown capture mode. Allowed modes: ['step', 'turn']"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Ascii,
+    Asciicast,
+    Gif,
+}
+
+impl RecordFormat {
+    pub fn parse(format: &str) -> Result<RecordFormat, &'static str> {
+        match format.to_lowercase().as_str() {
+            "ascii" => Ok(RecordFormat::Ascii),
+            "asciicast" => Ok(RecordFormat::Asciicast),
+            "gif" => Ok(RecordFormat::Gif),
+            _ => Err("Unknown recording format. Allowed formats: ['ascii', 'asciicast', 'gif']"),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Ascii => "txt",
+            RecordFormat::Asciicast => "cast",
+            RecordFormat::Gif => "gif",
+        }
+    }
+}
+
+/// Frames of a patrol, each `m` rows of `n` tiles. Guards are drawn facing
+/// their current direction; the final frame drops the guards and marks
+/// every visited cell with `X`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub m: usize,
+    pub n: usize,
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl Lab {
+    /// Moves all guards in lockstep, capturing frames until every guard has
+    /// left the map or been seen going round its loop once, or until
+    /// `max_frames` frames have been captured. The first and the final frame
+    /// are always kept, so at least two frames are captured.
+    pub fn record(&self, capture: Capture, max_frames: usize) -> Recording {
+        let cells = self.tiles.len();
+        let mut floor = self.tiles.clone();
+        for guard in &self.guards {
+            floor[self.index(guard.position)] = b'.';
+        }
+
+        let mut guards: Vec<_> = self
+            .guards
+            .iter()
+            .map(|guard| Some((self.index(guard.position), guard.facing)))
+            .collect();
+        let mut seen: Vec<BitSet> = vec![BitSet::new(4 * cells); guards.len()];
+        let mut looped = vec![false; guards.len()];
+        let mut visited = BitSet::new(cells);
+
+        let draw = |guards: &[Option<(usize, Direction)>]| {
+            let mut frame = floor.clone();
+            for &(position, direction) in guards.iter().flatten() {
+                frame[position] = direction.glyph();
+            }
+            frame
+        };
+
+        let mut frames = vec![draw(&guards)];
+        for (idx, guard) in self.guards.iter().enumerate() {
+            let position = self.index(guard.position);
+            visited.insert(position);
+            seen[idx].insert(guard.facing as usize * cells + position);
+        }
+
+        while frames.len() + 1 < max_frames.max(2) {
+            let finished = guards
+                .iter()
+                .zip(&looped)
+                .all(|(guard, &looped)| guard.is_none() || looped);
+            if finished {
+                break;
+            }
+
+            let mut turned = false;
+            for (idx, guard) in guards.iter_mut().enumerate() {
+                let Some((position, direction)) = *guard else {
+                    continue;
+                };
+                *guard = self.tick(position, direction, self.guards[idx].turn, &[]);
+                if let Some((position, facing)) = *guard {
+                    visited.insert(position);
+                    turned |= facing != direction;
+
+                    let state = facing as usize * cells + position;
+                    looped[idx] |= seen[idx].contains(state);
+                    seen[idx].insert(state);
+                }
+            }
+
+            if capture == Capture::Step || turned {
+                frames.push(draw(&guards));
+            }
+        }
+
+        let mut last = floor;
+        for position in visited.iter() {
+            last[position] = b'X';
+        }
+        frames.push(last);
+
+        Recording {
+            m: self.m,
+            n: self.n,
+            frames,
+        }
+    }
+}
+
+impl Recording {
+    fn rows<'a>(&'a self, frame: &'a [u8]) -> impl Iterator<Item = &'a str> + 'a {
+        frame
+            .chunks(self.n.max(1))
+            .map(|row| std::str::from_utf8(row).unwrap())
+    }
+
+    /// The frames as plain text, separated by blank lines.
+    pub fn to_ascii(&self) -> String {
+        self.frames
+            .iter()
+            .map(|frame| {
+                let mut text: String = self.rows(frame).collect::<Vec<_>>().join("\n");
+                text.push('\n');
+                text
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// An asciicast v2 file showing one frame every `delay` seconds.
+    pub fn to_asciicast(&self, delay: f64) -> String {
+        let mut cast = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}\n",
+            self.n, self.m
+        );
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let screen = format!(
+                "\x1b[H\x1b[2J{}",
+                self.rows(frame).collect::<Vec<_>>().join("\r\n")
+            );
+            cast.push_str(&format!(
+                "[{:.3}, \"o\", \"{}\"]\n",
+                idx as f64 * delay,
+                escape_json(&screen)
+            ));
+        }
+        cast
+    }
+
+    /// An animated GIF drawing every tile as a `scale` by `scale` block and
+    /// showing each frame for `delay` hundredths of a second. The animation
+    /// loops forever. Fails if the scaled map is too large for a GIF.
+    pub fn to_gif(&self, scale: usize, delay: u16) -> Result<Vec<u8>, &'static str> {
+        let too_large = "The scaled map is too large for a GIF.";
+        let width = self
+            .n
+            .checked_mul(scale)
+            .and_then(|width| u16::try_from(width).ok())
+            .ok_or(too_large)?;
+        let height = self
+            .m
+            .checked_mul(scale)
+            .and_then(|height| u16::try_from(height).ok())
+            .ok_or(too_large)?;
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        // Global colour table of 8 entries, no background colour or aspect.
+        gif.extend([0xf2, 0, 0]);
+        gif.extend(PALETTE.iter().flatten());
+        // Application extension asking viewers to loop forever.
+        gif.extend([0x21, 0xff, 0x0b]);
+        gif.extend(b"NETSCAPE2.0");
+        gif.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for frame in &self.frames {
+            gif.extend([0x21, 0xf9, 0x04, 0x00]);
+            gif.extend(delay.to_le_bytes());
+            gif.extend([0x00, 0x00]);
+
+            gif.push(0x2c);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend(width.to_le_bytes());
+            gif.extend(height.to_le_bytes());
+            gif.push(0x00);
+
+            let mut pixels = Vec::with_capacity(width as usize * height as usize);
+            for row in frame.chunks(self.n.max(1)) {
+                let line: Vec<u8> = row
+                    .iter()
+                    .flat_map(|&tile| std::iter::repeat_n(colour(tile), scale))
+                    .collect();
+                for _ in 0..scale {
+                    pixels.extend(&line);
+                }
+            }
+
+            gif.push(MIN_CODE_SIZE);
+            for block in lzw_uncompressed(&pixels).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0x00);
+        }
+
+        gif.push(0x3b);
+        Ok(gif)
+    }
+}
+
+const PALETTE: [[u8; 3]; 8] = [
+    [0xf4, 0xf4, 0xf4],
+    [0x33, 0x33, 0x33],
+    [0xd6, 0x27, 0x28],
+    [0x6b, 0xae, 0xd6],
+    [0xff, 0x7f, 0x0e],
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00],
+];
+
+fn colour(tile: u8) -> u8 {
+    match tile {
+        b'X' => 3,
+        b'^' | b'>' | b'v' | b'<' => 2,
+        _ => match crate::classify(tile) {
+            Some(Tile::Floor) => 0,
+            Some(Tile::Obstacle) => 1,
+            _ => 4,
+        },
+    }
+}
+
+const MIN_CODE_SIZE: u8 = 3;
+
+/// LZW image data that never lets the code table grow: a clear code is sent
+/// before every six pixels, so every code stays four bits wide. Larger than a
+/// real compressor's output, but valid for any decoder.
+fn lzw_uncompressed(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let width = MIN_CODE_SIZE as u32 + 1;
+
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits: u32 = 0;
+    let mut emit = |code: u16| {
+        buffer |= (code as u32) << bits;
+        bits += width;
+        while bits >= 8 {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+
+    for chunk in pixels.chunks((clear - 2) as usize) {
+        emit(clear);
+        for &pixel in chunk {
+            emit(pixel as u16);
+        }
+    }
+    emit(end);
+    if bits > 0 {
+        bytes.push(buffer as u8);
+    }
+
+    bytes
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(recording: &Recording) -> Vec<&str> {
+        recording
+            .frames
+            .iter()
+            .map(|frame| std::str::from_utf8(frame).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn check_record() {
+        let lab = Lab::build(".#.\n.^.").unwrap();
+
+        let recording = lab.record(Capture::Step, 100);
+        assert_eq!(
+            frames(&recording),
+            vec![".#..^.", ".#..>.", ".#...>", ".#....", ".#..XX"]
+        );
+
+        let recording = lab.record(Capture::Turn, 100);
+        assert_eq!(frames(&recording), vec![".#..^.", ".#..>.", ".#..XX"]);
+        assert_eq!(recording.to_ascii(), ".#.\n.^.\n\n.#.\n.>.\n\n.#.\n.XX\n");
+    }
+
+    #[test]
+    fn check_record_loop() {
+        let mut lab = Lab::build("#\n.\n^\n.\n#").unwrap();
+        lab.guards[0].turn = crate::Turn::Reverse;
+
+        // Up, turn, down, down, turn, up: back in the starting state.
+        let recording = lab.record(Capture::Step, 100);
+        assert_eq!(recording.frames.len(), 8);
+        assert_eq!(frames(&recording).last(), Some(&"#XXX#"));
+
+        assert_eq!(lab.record(Capture::Step, 4).frames.len(), 4);
+        assert_eq!(lab.record(Capture::Step, 1).frames.len(), 2);
+        assert_eq!(lab.record(Capture::Step, 0).frames.len(), 2);
+    }
+
+    #[test]
+    fn check_asciicast() {
+        let lab = Lab::build("\\^").unwrap();
+        let cast = lab.record(Capture::Step, 100).to_asciicast(0.5);
+        let lines: Vec<&str> = cast.lines().collect();
+
+        assert_eq!(lines[0], "{\"version\": 2, \"width\": 2, \"height\": 1}");
+        assert_eq!(lines[1], "[0.000, \"o\", \"\\u001b[H\\u001b[2J\\\\^\"]");
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("[0.500, "));
+    }
+
+    /// Decodes image data made of fixed four bit codes.
+    fn decode(data: &[u8]) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        for &byte in data {
+            for code in [byte & 0x0f, byte >> 4] {
+                match code {
+                    8 => continue,
+                    9 => return pixels,
+                    code => pixels.push(code),
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn check_gif() {
+        let lab = Lab::build("#.\n.^").unwrap();
+        let recording = lab.record(Capture::Step, 100);
+        let gif = recording.to_gif(2, 10).unwrap();
+
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(&gif[6..10], &[4, 0, 4, 0]);
+        assert_eq!(gif.last(), Some(&0x3b));
+        assert_eq!(
+            gif.windows(4)
+                .filter(|bytes| bytes == &[0x21, 0xf9, 0x04, 0x00])
+                .count(),
+            recording.frames.len()
+        );
+
+        let pixels = [1, 1, 0, 0, 1, 1, 0, 0, 0, 0, 2, 2, 0, 0, 2, 2];
+        let data = lzw_uncompressed(&pixels);
+        assert_eq!(decode(&data), pixels);
+
+        assert!(recording.to_gif(usize::MAX, 10).is_err());
+        assert!(recording.to_gif(40_000, 10).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::bitset::BitSet;
+use crate::walk::UNSEEN;
+use crate::{parallel_map, Guard, Lab, Position};
+
+/// How to print the per-obstruction loop report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    /// The map with every loop-causing obstruction drawn as `O`.
+    Map,
+}
+
+impl ReportFormat {
+    pub fn parse(format: &str) -> Result<ReportFormat, &'static str> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            "map" => Ok(ReportFormat::Map),
+            _ => Err("Unknown report format. Allowed formats: ['csv', 'json', 'map']"),
+        }
+    }
+}
+
+/// A guard caught in a loop. Steps count moves and turns on the spot alike.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap {
+    pub guard: usize,
+    /// Steps taken before the guard first reaches a state on the loop.
+    pub entry_step: usize,
+    /// Steps needed to go round the loop once.
+    pub length: usize,
+    /// Cells on the loop, in reading order.
+    pub tiles: Vec<Position>,
+}
+
+/// What a single new obstruction does to the patrols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateReport {
+    pub obstruction: Position,
+    /// The first guard, in reading order, trapped by the obstruction.
+    pub trap: Option<Trap>,
+}
+
+impl Lab {
+    /// Details of the loop the guard ends up in once `obstructions` are
+    /// added, or `None` if it leaves the map. Fails for an unknown guard, or
+    /// an obstruction off the map or on the guard's start.
+    pub fn trap(
+        &self,
+        guard: usize,
+        obstructions: &[Position],
+    ) -> Result<Option<Trap>, &'static str> {
+        if guard >= self.guards.len() {
+            return Err("No such guard on the map.");
+        }
+        if !obstructions
+            .iter()
+            .all(|&Position(i, j)| i < self.m && j < self.n)
+        {
+            return Err("Obstruction is off the map.");
+        }
+
+        let obstructions: Vec<usize> = obstructions.iter().map(|&cell| self.index(cell)).collect();
+        if obstructions.contains(&self.index(self.guards[guard].position)) {
+            return Err("Start position is obstructed.");
+        }
+        let mut first_seen = vec![UNSEEN; 4 * self.tiles.len()];
+        Ok(self.trap_with(guard, &obstructions, &mut first_seen))
+    }
+
+    fn trap_with(
+        &self,
+        guard: usize,
+        obstructions: &[usize],
+        first_seen: &mut [u32],
+    ) -> Option<Trap> {
+        let cells = self.tiles.len();
+        let Guard {
+            position,
+            facing,
+            turn,
+        } = self.guards[guard];
+        let trace = self.trace(self.index(position), facing, turn, obstructions, first_seen);
+        let entry_step = trace.entry_step?;
+
+        let mut on_loop = BitSet::new(cells);
+        for &state in &trace.states[entry_step..] {
+            on_loop.insert(state % cells);
+        }
+
+        Some(Trap {
+            guard,
+            entry_step,
+            length: trace.states.len() - entry_step,
+            tiles: on_loop.iter().map(|idx| self.position(idx)).collect(),
+        })
+    }
+
+    /// For every cell where a new obstruction could change a patrol, whether
+    /// it traps a guard that would otherwise leave, and the loop it causes.
+    pub fn loop_report(&self) -> Vec<CandidateReport> {
+        let (leaving, candidates) = self.obstruction_candidates();
+        let cells = self.tiles.len();
+
+        parallel_map(
+            &candidates,
+            || (BitSet::new(8 * cells), vec![UNSEEN; 4 * cells]),
+            |&obstruction, (seen, first_seen)| {
+                let trap = leaving
+                    .iter()
+                    .find(|&&idx| self.loops_with(&self.guards[idx], &[obstruction], seen))
+                    .and_then(|&idx| self.trap_with(idx, &[obstruction], first_seen));
+
+                CandidateReport {
+                    obstruction: self.position(obstruction),
+                    trap,
+                }
+            },
+        )
+    }
+
+    /// The map with every loop-causing obstruction from `reports` drawn as
+    /// `O`.
+    pub fn render_loop_obstructions(&self, reports: &[CandidateReport]) -> String {
+        let mut tiles = self.tiles.clone();
+        for report in reports.iter().filter(|report| report.trap.is_some()) {
+            tiles[self.index(report.obstruction)] = b'O';
+        }
+
+        tiles
+            .chunks(self.n.max(1))
+            .map(|row| format!("{}\n", std::str::from_utf8(row).unwrap()))
+            .collect()
+    }
+}
+
+fn format_tiles(tiles: &[Position], format: impl Fn(&Position) -> String, sep: &str) -> String {
+    tiles.iter().map(format).collect::<Vec<_>>().join(sep)
+}
+
+/// One line per candidate; loop tiles are written as `row:col` separated by
+/// spaces.
+pub fn to_csv(reports: &[CandidateReport]) -> String {
+    let mut csv = String::from("row,col,loops,guard,entry_step,loop_length,loop_tiles\n");
+    for report in reports {
+        let Position(row, col) = report.obstruction;
+        match &report.trap {
+            Some(trap) => csv.push_str(&format!(
+                "{},{},true,{},{},{},{}\n",
+                row,
+                col,
+                trap.guard,
+                trap.entry_step,
+                trap.length,
+                format_tiles(&trap.tiles, |Position(i, j)| format!("{}:{}", i, j), " ")
+            )),
+            None => csv.push_str(&format!("{},{},false,,,,\n", row, col)),
+        }
+    }
+    csv
+}
+
+pub fn to_json(reports: &[CandidateReport]) -> String {
+    let entries: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            let Position(row, col) = report.obstruction;
+            match &report.trap {
+                Some(trap) => format!(
+                    "  {{\"row\": {}, \"col\": {}, \"loops\": true, \"guard\": {}, \"entry_step\": {}, \"loop_length\": {}, \"loop_tiles\": [{}]}}",
+                    row,
+                    col,
+                    trap.guard,
+                    trap.entry_step,
+                    trap.length,
+                    format_tiles(&trap.tiles, |Position(i, j)| format!("[{}, {}]", i, j), ", ")
+                ),
+                None => format!("  {{\"row\": {}, \"col\": {}, \"loops\": false}}", row, col),
+            }
+        })
+        .collect();
+
+    if entries.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", entries.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+
+    #[test]
+    fn check_trap() {
+        let lab = Lab::build(".#...\n....#\n.....\n#....\n.^.#.").unwrap();
+        let trap = lab.trap(0, &[]).unwrap().unwrap();
+
+        assert_eq!(trap.entry_step, 1);
+        assert_eq!(trap.length, 12);
+        assert_eq!(
+            trap.tiles,
+            vec![
+                Position(1, 1),
+                Position(1, 2),
+                Position(1, 3),
+                Position(2, 1),
+                Position(2, 3),
+                Position(3, 1),
+                Position(3, 2),
+                Position(3, 3),
+            ]
+        );
+
+        assert_eq!(lab.trap(0, &[Position(2, 1)]), Ok(None));
+        assert!(lab.trap(0, &[Position(5, 0)]).is_err());
+        assert!(lab.trap(1, &[]).is_err());
+        assert_eq!(
+            lab.trap(0, &[Position(4, 1)]),
+            Err("Start position is obstructed.")
+        );
+    }
+
+    #[test]
+    fn check_loop_report() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        let reports = lab.loop_report();
+
+        assert_eq!(reports.len(), 40);
+        let looping: Vec<Position> = reports
+            .iter()
+            .filter(|report| report.trap.is_some())
+            .map(|report| report.obstruction)
+            .collect();
+        assert_eq!(
+            looping,
+            vec![
+                Position(6, 3),
+                Position(7, 6),
+                Position(7, 7),
+                Position(8, 1),
+                Position(8, 3),
+                Position(9, 7),
+            ]
+        );
+
+        let map = lab.render_loop_obstructions(&reports);
+        assert_eq!(map.lines().nth(6), Some(".#.O^....."));
+        assert_eq!(map.lines().nth(9), Some("......#O.."));
+    }
+
+    #[test]
+    fn check_exports() {
+        let reports = vec![
+            CandidateReport {
+                obstruction: Position(0, 1),
+                trap: None,
+            },
+            CandidateReport {
+                obstruction: Position(2, 3),
+                trap: Some(Trap {
+                    guard: 0,
+                    entry_step: 4,
+                    length: 2,
+                    tiles: vec![Position(1, 1), Position(1, 2)],
+                }),
+            },
+        ];
+
+        assert_eq!(
+            to_csv(&reports),
+            "row,col,loops,guard,entry_step,loop_length,loop_tiles\n0,1,false,,,,\n2,3,true,0,4,2,1:1 1:2\n"
+        );
+        assert_eq!(
+            to_json(&reports),
+            "[\n  {\"row\": 0, \"col\": 1, \"loops\": false},\n  {\"row\": 2, \"col\": 3, \"loops\": true, \"guard\": 0, \"entry_step\": 4, \"loop_length\": 2, \"loop_tiles\": [[1, 1], [1, 2]]}\n]\n"
+        );
+    }
+}
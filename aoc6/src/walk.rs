@@ -0,0 +1,229 @@
+use crate::bitset::BitSet;
+use crate::{Direction, Guard, Lab, Position, Tile, Turn};
+
+/// How a walk ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The guard steps off the map from `position`, heading `direction`.
+    Exit {
+        position: Position,
+        direction: Direction,
+    },
+    /// The guard is trapped: from step `entry_step` on it repeats the same
+    /// `length` steps forever.
+    Loop { entry_step: usize, length: usize },
+}
+
+/// The route of a single guard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    pub outcome: Outcome,
+    /// Steps taken, moves and turns on the spot alike. For a guard that
+    /// leaves this includes the step off the map; for a trapped guard it
+    /// counts the steps until a state first repeats.
+    pub steps: usize,
+    /// Cells the guard stands on, in order of the first visit.
+    pub tiles: Vec<Position>,
+}
+
+/// Marker for states the guard has not been in yet.
+pub(crate) const UNSEEN: u32 = u32::MAX;
+
+/// Every state of a step by step walk, as `direction * m * n + cell`, and
+/// the step at which the guard enters its loop, if it has one.
+pub(crate) struct Trace {
+    pub states: Vec<usize>,
+    pub entry_step: Option<usize>,
+}
+
+impl Lab {
+    /// Follows a guard starting at `guard.position` until it leaves the map
+    /// or repeats itself, with `obstructions` added to the map for this walk
+    /// only. The guard must start on a free floor tile.
+    pub fn walk(&self, guard: &Guard, obstructions: &[Position]) -> Result<Walk, &'static str> {
+        let on_map = |Position(i, j): Position| i < self.m && j < self.n;
+        if !on_map(guard.position) {
+            return Err("Start position is off the map.");
+        }
+        if !obstructions.iter().all(|&cell| on_map(cell)) {
+            return Err("Obstruction is off the map.");
+        }
+
+        let start = self.index(guard.position);
+        let obstructions: Vec<usize> = obstructions.iter().map(|&cell| self.index(cell)).collect();
+        if obstructions.contains(&start) {
+            return Err("Start position is obstructed.");
+        }
+        if self.tile(start) != Tile::Floor {
+            return Err("Start position is not on the floor.");
+        }
+
+        let mut first_seen = vec![UNSEEN; 4 * self.tiles.len()];
+        let trace = self.trace(
+            start,
+            guard.facing,
+            guard.turn,
+            &obstructions,
+            &mut first_seen,
+        );
+
+        let cells = self.tiles.len();
+        let mut seen = BitSet::new(cells);
+        let mut tiles = Vec::new();
+        for &state in &trace.states {
+            let cell = state % cells;
+            if !seen.contains(cell) {
+                seen.insert(cell);
+                tiles.push(self.position(cell));
+            }
+        }
+
+        let steps = trace.states.len();
+        let outcome = match trace.entry_step {
+            Some(entry_step) => Outcome::Loop {
+                entry_step,
+                length: steps - entry_step,
+            },
+            None => {
+                let last = *trace.states.last().unwrap();
+                Outcome::Exit {
+                    position: self.position(last % cells),
+                    direction: Direction::ALL[last / cells],
+                }
+            }
+        };
+
+        Ok(Walk {
+            outcome,
+            steps,
+            tiles,
+        })
+    }
+
+    /// Walks step by step, remembering the step at which each state was
+    /// first reached. `first_seen` must hold `4 * m * n` entries, all
+    /// `UNSEEN`, and is left that way.
+    pub(crate) fn trace(
+        &self,
+        start: usize,
+        facing: Direction,
+        turn: Turn,
+        obstructions: &[usize],
+        first_seen: &mut [u32],
+    ) -> Trace {
+        let cells = self.tiles.len();
+        let mut position = start;
+        let mut direction = facing;
+        let mut states: Vec<usize> = Vec::new();
+
+        let entry_step = loop {
+            let state = direction as usize * cells + position;
+            if first_seen[state] != UNSEEN {
+                break Some(first_seen[state] as usize);
+            }
+            first_seen[state] = states.len() as u32;
+            states.push(state);
+
+            match self.tick(position, direction, turn, obstructions) {
+                Some((next, facing)) => {
+                    position = next;
+                    direction = facing;
+                }
+                None => break None,
+            }
+        };
+
+        for &state in &states {
+            first_seen[state] = UNSEEN;
+        }
+
+        Trace { states, entry_step }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+
+    fn guard(row: usize, col: usize, facing: Direction) -> Guard {
+        Guard {
+            position: Position(row, col),
+            facing,
+            turn: Turn::Right,
+        }
+    }
+
+    #[test]
+    fn check_walk() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        let walk = lab.walk(&lab.guards[0], &[]).unwrap();
+
+        assert_eq!(
+            walk.outcome,
+            Outcome::Exit {
+                position: Position(9, 7),
+                direction: Direction::Down,
+            }
+        );
+        assert_eq!(walk.tiles.len(), 41);
+        assert_eq!(walk.tiles[0], Position(6, 4));
+        assert_eq!(walk.steps, 55);
+
+        let walk = lab.walk(&guard(0, 0, Direction::Right), &[]).unwrap();
+        assert_eq!(
+            walk.outcome,
+            Outcome::Exit {
+                position: Position(9, 3),
+                direction: Direction::Down,
+            }
+        );
+        assert_eq!(walk.steps, 14);
+    }
+
+    #[test]
+    fn check_walk_with_obstructions() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+        let walk = lab.walk(&lab.guards[0], &[Position(6, 3)]).unwrap();
+
+        assert!(matches!(walk.outcome, Outcome::Loop { .. }));
+        assert_eq!(
+            lab.walk(&lab.guards[0], &[]).unwrap().outcome,
+            Outcome::Exit {
+                position: Position(9, 7),
+                direction: Direction::Down,
+            }
+        );
+
+        let walk = lab
+            .walk(&guard(6, 4, Direction::Up), &[Position(5, 4)])
+            .unwrap();
+        assert_eq!(walk.tiles[1], Position(6, 5));
+    }
+
+    #[test]
+    fn check_walk_errors() {
+        let lab = Lab::build(EXAMPLE).unwrap();
+
+        assert!(lab.walk(&guard(10, 0, Direction::Up), &[]).is_err());
+        assert!(lab
+            .walk(&guard(0, 0, Direction::Up), &[Position(0, 10)])
+            .is_err());
+        assert!(lab
+            .walk(&guard(0, 0, Direction::Up), &[Position(0, 0)])
+            .is_err());
+    }
+
+    #[test]
+    fn check_walk_from_non_floor() {
+        let lab = Lab::build("#N/a\n.^.a").unwrap();
+
+        for col in 0..4 {
+            assert_eq!(
+                lab.walk(&guard(0, col, Direction::Up), &[]),
+                Err("Start position is not on the floor.")
+            );
+        }
+        assert!(lab.walk(&guard(1, 0, Direction::Up), &[]).is_ok());
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use crate::number::{BigUint, Number};
+use crate::operator::{Operator, Range};
+use crate::Equation;
+
+/// Number of assignments that take the first `i + 1` numbers into a range,
+/// keyed by `i` and the range.
+type Counts<N> = HashMap<(usize, Range<N>), BigUint>;
+
+impl<N: Number> Equation<N> {
+    /// Number of distinct operator assignments from `operators` that give
+    /// the target.
+    ///
+    /// Works backwards like `solve`, remembering the count for every value
+    /// range it reaches, so that prefixes ending in the same value are only
+    /// counted once.
+    pub fn count_solutions(&self, operators: &[Box<dyn Operator<N>>]) -> BigUint {
+        let Some(last) = self.numbers.len().checked_sub(1) else {
+            return BigUint::zero();
+        };
+        let mut counts = Counts::new();
+        let target = Range::single(self.target.clone());
+        self.count_from(last, &target, operators, &mut counts)
+    }
+
+    /// Every operator assignment from `operators` that gives the target.
+    pub fn solutions<'a>(
+        &self,
+        operators: &'a [Box<dyn Operator<N>>],
+    ) -> Vec<Vec<&'a dyn Operator<N>>> {
+        let Some(last) = self.numbers.len().checked_sub(1) else {
+            return Vec::new();
+        };
+        let mut counts = Counts::new();
+        let target = Range::single(self.target.clone());
+        self.solutions_from(last, &target, operators, &mut counts)
+    }
+
+    fn count_from(
+        &self,
+        i: usize,
+        results: &Range<N>,
+        operators: &[Box<dyn Operator<N>>],
+        counts: &mut Counts<N>,
+    ) -> BigUint {
+        if i == 0 {
+            return BigUint::from_u64(results.contains(&self.numbers[0]) as u64);
+        }
+        let key = (i, results.clone());
+        if let Some(count) = counts.get(&key) {
+            return count.clone();
+        }
+
+        let mut count = BigUint::zero();
+        for operator in operators {
+            for range in operator.undo(results, &self.numbers[i]) {
+                let ways = self.count_from(i - 1, &range, operators, counts);
+                count = count.checked_add(&ways).unwrap();
+            }
+        }
+        counts.insert(key, count.clone());
+        count
+    }
+
+    /// Like `count_from`, but lists the assignments. Ranges that cannot be
+    /// reached are skipped using the counts.
+    fn solutions_from<'a>(
+        &self,
+        i: usize,
+        results: &Range<N>,
+        operators: &'a [Box<dyn Operator<N>>],
+        counts: &mut Counts<N>,
+    ) -> Vec<Vec<&'a dyn Operator<N>>> {
+        if i == 0 {
+            return if results.contains(&self.numbers[0]) {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            };
+        }
+
+        let mut solutions = Vec::new();
+        for operator in operators {
+            for range in operator.undo(results, &self.numbers[i]) {
+                if self.count_from(i - 1, &range, operators, counts).is_zero() {
+                    continue;
+                }
+                for mut solution in self.solutions_from(i - 1, &range, operators, counts) {
+                    solution.push(operator.as_ref());
+                    solutions.push(solution);
+                }
+            }
+        }
+        solutions
+    }
+}
+
+/// One line per equation with its number of solutions, each followed by
+/// its solutions if `list` is set.
+pub fn render_solutions<N: Number>(
+    equations: &[Equation<N>],
+    operators: &[Box<dyn Operator<N>>],
+    list: bool,
+) -> String {
+    let mut report = String::new();
+    for equation in equations {
+        let count = equation.count_solutions(operators);
+        let plural = if count == BigUint::from_u64(1) {
+            ""
+        } else {
+            "s"
+        };
+        report.push_str(&format!("{} ({} solution{})\n", equation, count, plural));
+
+        if list {
+            for solution in equation.solutions(operators) {
+                report.push_str(&format!("    {}\n", equation.expression(&solution)));
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_operators, part_one, part_two};
+
+    /// Number of assignments that give the target, trying them all.
+    fn brute_force(equation: &Equation, operators: &[Box<dyn Operator<u64>>]) -> u64 {
+        let mut values = vec![equation.numbers[0]];
+        for &y in &equation.numbers[1..] {
+            values = values
+                .iter()
+                .flat_map(|&x| {
+                    operators
+                        .iter()
+                        .filter_map(move |operator| operator.apply(&x, &y))
+                })
+                .collect();
+        }
+        values
+            .iter()
+            .filter(|&&value| value == equation.target)
+            .count() as u64
+    }
+
+    #[test]
+    fn check_count_solutions() {
+        let equation = Equation::<u64>::parse("3267: 81 40 27").unwrap();
+        assert_eq!(equation.count_solutions(&part_one()), BigUint::from_u64(2));
+
+        let operators = part_one();
+        let expressions: Vec<String> = equation
+            .solutions(&operators)
+            .iter()
+            .map(|solution| equation.expression(solution))
+            .collect();
+        assert_eq!(expressions, ["81 * 40 + 27", "81 + 40 * 27"]);
+
+        // The final `* 0` accepts all 2^39 assignments before it; they are
+        // counted without being enumerated.
+        let equation = Equation::<u64>::parse(&format!("0: {}0", "1 ".repeat(40))).unwrap();
+        assert_eq!(
+            equation.count_solutions(&part_one()),
+            BigUint::parse(&(1_u64 << 39).to_string()).unwrap()
+        );
+    }
+
+    #[test]
+    fn check_against_brute_force() {
+        let sets = [
+            part_one(),
+            part_two(),
+            parse_operators("add,sub,mul,div,xor").unwrap(),
+            parse_operators("pow,cat2,mul").unwrap(),
+        ];
+        for line in [
+            "12: 2 2 2 2 2",
+            "4: 2 2 1 2",
+            "8: 4 2 1 1 2 1",
+            "1: 1 1 1 1",
+        ] {
+            let equation = Equation::parse(line).unwrap();
+            for operators in &sets {
+                let count = brute_force(&equation, operators);
+                assert_eq!(
+                    equation.count_solutions(operators),
+                    BigUint::from_u64(count)
+                );
+
+                let solutions = equation.solutions(operators);
+                assert_eq!(solutions.len() as u64, count);
+                for solution in &solutions {
+                    assert_eq!(equation.evaluate(solution), Some(equation.target));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_render_solutions() {
+        let equations: Vec<Equation> = ["3267: 81 40 27", "83: 17 5", "190: 10 19"]
+            .iter()
+            .map(|line| Equation::parse(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            render_solutions(&equations, &part_one(), false),
+            "3267: 81 40 27 (2 solutions)\n83: 17 5 (0 solutions)\n190: 10 19 (1 solution)\n"
+        );
+        assert_eq!(
+            render_solutions(&equations[2..], &part_one(), true),
+            "190: 10 19 (1 solution)\n    10 * 19\n"
+        );
+    }
+}
@@ -0,0 +1,554 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+use crate::number::Number;
+use crate::operator::{Operator, Range};
+use crate::Equation;
+
+/// How the operators of an equation are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evaluation {
+    /// Strictly left to right, as in the puzzle.
+    LeftToRight,
+    /// Tighter binding operators first, see `Operator::precedence`.
+    Precedence,
+    /// Any way of parenthesising the expression.
+    Parenthesised,
+}
+
+impl Evaluation {
+    pub fn parse(evaluation: &str) -> Result<Evaluation, &'static str> {
+        match evaluation.to_lowercase().as_str() {
+            "left" => Ok(Evaluation::LeftToRight),
+            "precedence" => Ok(Evaluation::Precedence),
+            "parens" => Ok(Evaluation::Parenthesised),
+            _ => Err("Unknown evaluation. Allowed evaluations: ['left', 'precedence', 'parens']"),
+        }
+    }
+}
+
+/// An expression evaluated with precedence up to the last number: operands
+/// still waiting for their right-hand side, and the operators between them.
+#[derive(Clone)]
+struct Pending<'a, N> {
+    values: Vec<N>,
+    operators: Vec<&'a dyn Operator<N>>,
+}
+
+impl<'a, N: Number> Pending<'a, N> {
+    /// Applies the pending operators that bind at least as tightly as
+    /// `next`, or all of them if there is no next operator. Fails if a step
+    /// is undefined or gives a value above `bound`.
+    fn reduce(&mut self, next: Option<&dyn Operator<N>>, bound: Option<&N>) -> Option<()> {
+        while let Some(&top) = self.operators.last() {
+            if let Some(next) = next {
+                let binds = match top.precedence().cmp(&next.precedence()) {
+                    Ordering::Greater => true,
+                    Ordering::Equal => !next.right_associative(),
+                    Ordering::Less => false,
+                };
+                if !binds {
+                    break;
+                }
+            }
+
+            self.operators.pop();
+            let y = self.values.pop().unwrap();
+            let x = self.values.pop().unwrap();
+            let value = top.apply(&x, &y)?;
+            if bound.is_some_and(|bound| value > *bound) {
+                return None;
+            }
+            self.values.push(value);
+        }
+        Some(())
+    }
+}
+
+/// Whether a run of numbers, keyed by its first and last index, can give a
+/// value in a range.
+type Reachable<N> = HashMap<(usize, usize, Range<N>), bool>;
+
+/// A way to split a run of numbers: the split point, the operator and the
+/// values sought from both parts.
+type Split<N> = (usize, usize, Range<N>, Range<N>);
+
+/// Searches the parenthesisations of an equation from the target down.
+///
+/// Only runs of up to half the numbers have their values listed. A longer run
+/// is split in two; each value of the shorter part is tried in turn, and the
+/// longer part is searched for the values that complete it.
+struct Parentheses<'a, N: Number> {
+    numbers: &'a [N],
+    operators: &'a [Box<dyn Operator<N>>],
+    /// Length of the longest run with listed values.
+    listed: usize,
+    /// `values[i][j]` holds the values of the numbers `i..=j`, for listed
+    /// runs.
+    values: Vec<Vec<BTreeSet<N>>>,
+}
+
+impl<'a, N: Number> Parentheses<'a, N> {
+    /// Lists the values of every short run, dropping those above `bound`.
+    fn new(
+        numbers: &'a [N],
+        operators: &'a [Box<dyn Operator<N>>],
+        bound: Option<&N>,
+    ) -> Parentheses<'a, N> {
+        let len = numbers.len();
+        let listed = len.div_ceil(2);
+        let mut values = vec![vec![BTreeSet::new(); len]; len];
+        for (i, number) in numbers.iter().enumerate() {
+            values[i][i].insert(number.clone());
+        }
+
+        for span in 1..listed {
+            for i in 0..len - span {
+                let j = i + span;
+                let mut found = BTreeSet::new();
+                for k in i..j {
+                    for x in &values[i][k] {
+                        for y in &values[k + 1][j] {
+                            for operator in operators {
+                                match operator.apply(x, y) {
+                                    Some(value) if bound.is_none_or(|bound| value <= *bound) => {
+                                        found.insert(value);
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                }
+                values[i][j] = found;
+            }
+        }
+
+        Parentheses {
+            numbers,
+            operators,
+            listed,
+            values,
+        }
+    }
+
+    /// Whether the numbers `i..=j` can give a value in `goal`.
+    fn reaches(&self, i: usize, j: usize, goal: &Range<N>, reachable: &mut Reachable<N>) -> bool {
+        if goal.end.as_ref().is_some_and(|end| goal.start > *end) {
+            return false;
+        }
+        if j - i < self.listed {
+            let end = match &goal.end {
+                Some(end) => Bound::Included(end),
+                None => Bound::Unbounded,
+            };
+            return self.values[i][j]
+                .range((Bound::Included(&goal.start), end))
+                .next()
+                .is_some();
+        }
+
+        let key = (i, j, goal.clone());
+        if let Some(&reached) = reachable.get(&key) {
+            return reached;
+        }
+        let reached = self.split(i, j, goal, reachable).is_some();
+        reachable.insert(key, reached);
+        reached
+    }
+
+    /// The first way to split the numbers `i..=j` into two parts that give a
+    /// value in `goal`: the earliest split point, then the first operator,
+    /// then the smallest value of the shorter part.
+    fn split(
+        &self,
+        i: usize,
+        j: usize,
+        goal: &Range<N>,
+        reachable: &mut Reachable<N>,
+    ) -> Option<Split<N>> {
+        for k in i..j {
+            for (idx, operator) in self.operators.iter().enumerate() {
+                if k - i < j - k - 1 {
+                    for x in &self.values[i][k] {
+                        for range in operator.undo_right(goal, x) {
+                            if self.reaches(k + 1, j, &range, reachable) {
+                                return Some((k, idx, Range::single(x.clone()), range));
+                            }
+                        }
+                    }
+                } else {
+                    for y in &self.values[k + 1][j] {
+                        for range in operator.undo(goal, y) {
+                            if self.reaches(i, k, &range, reachable) {
+                                return Some((k, idx, range, Range::single(y.clone())));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// An expression of the numbers `i..=j` that gives a value in `goal`,
+    /// which they must be able to reach.
+    fn expression(
+        &self,
+        i: usize,
+        j: usize,
+        goal: &Range<N>,
+        reachable: &mut Reachable<N>,
+        outermost: bool,
+    ) -> String {
+        if i == j {
+            return self.numbers[i].to_string();
+        }
+
+        let (k, idx, left, right) = self.split(i, j, goal, reachable).unwrap();
+        let expression = format!(
+            "{} {} {}",
+            self.expression(i, k, &left, reachable, false),
+            self.operators[idx].symbol(),
+            self.expression(k + 1, j, &right, reachable, false)
+        );
+        if outermost {
+            expression
+        } else {
+            format!("({})", expression)
+        }
+    }
+}
+
+impl<N: Number> Equation<N> {
+    /// An expression that gives the target under `evaluation`, using
+    /// operators from `operators`, if there is one.
+    pub fn witness(
+        &self,
+        operators: &[Box<dyn Operator<N>>],
+        evaluation: Evaluation,
+    ) -> Option<String> {
+        match evaluation {
+            Evaluation::LeftToRight => {
+                let solution = self.solve(operators)?;
+                Some(self.expression(&solution))
+            }
+            Evaluation::Precedence => {
+                let solution = self.solve_with_precedence(operators)?;
+                Some(self.expression(&solution))
+            }
+            Evaluation::Parenthesised => self.solve_with_parentheses(operators),
+        }
+    }
+
+    /// Evaluates the numbers with `operators` between them, applying tighter
+    /// binding operators first.
+    pub fn evaluate_with_precedence(&self, operators: &[&dyn Operator<N>]) -> Option<N> {
+        let (first, rest) = self.numbers.split_first()?;
+        let mut pending = Pending {
+            values: vec![first.clone()],
+            operators: Vec::new(),
+        };
+        for (y, &operator) in rest.iter().zip(operators) {
+            pending.reduce(Some(operator), None)?;
+            pending.operators.push(operator);
+            pending.values.push(y.clone());
+        }
+        pending.reduce(None, None)?;
+        pending.values.pop()
+    }
+
+    /// A bound on every partial result of an expression that gives the
+    /// target, if the operators and numbers allow one.
+    fn bound(&self, operators: &[Box<dyn Operator<N>>]) -> Option<&N> {
+        let one = N::from_u64(1);
+        let grows = operators.iter().all(|operator| operator.grows())
+            && self.numbers.iter().all(|number| *number >= one);
+        grows.then_some(&self.target)
+    }
+
+    /// Operators that give the target when evaluated with precedence.
+    ///
+    /// Tries assignments depth first, so the worst case is all
+    /// `operators.len() ^ (numbers.len() - 1)` of them. When every operator
+    /// grows, an assignment is abandoned as soon as a partial result exceeds
+    /// the target, and an equation with a number above the target is
+    /// rejected outright.
+    pub fn solve_with_precedence<'a>(
+        &self,
+        operators: &'a [Box<dyn Operator<N>>],
+    ) -> Option<Vec<&'a dyn Operator<N>>> {
+        let bound = self.bound(operators);
+        if let Some(bound) = bound {
+            if self.numbers.iter().any(|number| number > bound) {
+                return None;
+            }
+        }
+        let pending = Pending {
+            values: vec![self.numbers.first()?.clone()],
+            operators: Vec::new(),
+        };
+        let mut solution = Vec::with_capacity(self.numbers.len() - 1);
+        if self.precedence_from(1, pending, operators, bound, &mut solution) {
+            Some(solution)
+        } else {
+            None
+        }
+    }
+
+    fn precedence_from<'a>(
+        &self,
+        i: usize,
+        mut pending: Pending<'a, N>,
+        operators: &'a [Box<dyn Operator<N>>],
+        bound: Option<&N>,
+        solution: &mut Vec<&'a dyn Operator<N>>,
+    ) -> bool {
+        if i == self.numbers.len() {
+            return pending.reduce(None, bound).is_some() && pending.values[0] == self.target;
+        }
+
+        for operator in operators {
+            let mut next = pending.clone();
+            if next.reduce(Some(operator.as_ref()), bound).is_none() {
+                continue;
+            }
+            next.operators.push(operator.as_ref());
+            next.values.push(self.numbers[i].clone());
+
+            solution.push(operator.as_ref());
+            if self.precedence_from(i + 1, next, operators, bound, solution) {
+                return true;
+            }
+            solution.pop();
+        }
+        false
+    }
+
+    /// A fully parenthesised expression that gives the target.
+    ///
+    /// Searches from the target down, see `Parentheses`. When every operator
+    /// grows, values above the target are dropped.
+    pub fn solve_with_parentheses(&self, operators: &[Box<dyn Operator<N>>]) -> Option<String> {
+        let len = self.numbers.len();
+        if len == 0 {
+            return None;
+        }
+        let search = Parentheses::new(&self.numbers, operators, self.bound(operators));
+        let mut reachable = Reachable::new();
+        let target = Range::single(self.target.clone());
+        search
+            .reaches(0, len - 1, &target, &mut reachable)
+            .then(|| search.expression(0, len - 1, &target, &mut reachable, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_operators, part_one, part_two};
+    use std::iter::Peekable;
+
+    /// Evaluates a fully parenthesised expression of the second part's
+    /// operators, as written by `solve_with_parentheses`.
+    fn evaluate_parenthesised(expression: &str) -> Option<u64> {
+        let spaced = expression.replace('(', "( ").replace(')', " )");
+        let mut tokens = spaced.split_whitespace().peekable();
+        let value = parenthesised_from(&mut tokens)?;
+        tokens.next().is_none().then_some(value)
+    }
+
+    fn parenthesised_from<'a>(tokens: &mut Peekable<impl Iterator<Item = &'a str>>) -> Option<u64> {
+        let operand = |tokens: &mut Peekable<_>| match tokens.next()? {
+            "(" => {
+                let value = parenthesised_from(tokens)?;
+                (tokens.next()? == ")").then_some(value)
+            }
+            number => number.parse().ok(),
+        };
+
+        let x = operand(tokens)?;
+        match tokens.peek() {
+            Some(&symbol) if symbol != ")" => {
+                tokens.next();
+                let y = operand(tokens)?;
+                part_two::<u64>()
+                    .iter()
+                    .find(|operator| operator.symbol() == symbol)?
+                    .apply(&x, &y)
+            }
+            _ => Some(x),
+        }
+    }
+
+    /// Every assignment of `operators` to the gaps of `equation`.
+    fn assignments<'a>(
+        equation: &Equation,
+        operators: &'a [Box<dyn Operator<u64>>],
+    ) -> Vec<Vec<&'a dyn Operator<u64>>> {
+        let mut assignments = vec![Vec::new()];
+        for _ in 1..equation.numbers.len() {
+            assignments = assignments
+                .iter()
+                .flat_map(|prefix: &Vec<&'a dyn Operator<u64>>| {
+                    operators.iter().map(move |operator| {
+                        let mut assignment = prefix.clone();
+                        assignment.push(operator.as_ref());
+                        assignment
+                    })
+                })
+                .collect();
+        }
+        assignments
+    }
+
+    #[test]
+    fn check_evaluate_with_precedence() {
+        let equation = Equation::parse("0: 2 3 4 2 3").unwrap();
+        let operators = parse_operators("add,mul,pow,sub").unwrap();
+        let (add, mul, pow, sub) = (
+            operators[0].as_ref(),
+            operators[1].as_ref(),
+            operators[2].as_ref(),
+            operators[3].as_ref(),
+        );
+
+        // `^` groups to the right: 4 ^ 2 ^ 3 = 4 ^ 8.
+        assert_eq!(
+            equation.evaluate_with_precedence(&[add, mul, pow, pow]),
+            Some(2 + 3 * 65536)
+        );
+        assert_eq!(
+            equation.evaluate_with_precedence(&[mul, add, mul, sub]),
+            Some(2 * 3 + 4 * 2 - 3)
+        );
+        assert_eq!(
+            equation.evaluate_with_precedence(&[sub, sub, add, add]),
+            None
+        );
+    }
+
+    #[test]
+    fn check_precedence_against_brute_force() {
+        let sets = [
+            part_two(),
+            parse_operators("add,sub,mul,div").unwrap(),
+            parse_operators("mul,pow,xor,cat").unwrap(),
+        ];
+        for line in [
+            "14: 2 3 4",
+            "20: 2 3 4",
+            "24: 2 3 4",
+            "10: 2 2 3 2",
+            "7: 1 2 3 1",
+        ] {
+            let equation = Equation::parse(line).unwrap();
+            for operators in &sets {
+                let expected = assignments(&equation, operators).iter().any(|assignment| {
+                    equation.evaluate_with_precedence(assignment) == Some(equation.target)
+                });
+                let solution = equation.solve_with_precedence(operators);
+                assert_eq!(solution.is_some(), expected, "{}", line);
+                if let Some(solution) = solution {
+                    assert_eq!(
+                        equation.evaluate_with_precedence(&solution),
+                        Some(equation.target)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_precedence_rejects_large_numbers() {
+        let line = format!("50:{} 100", " 1".repeat(40));
+        let equation = Equation::<u64>::parse(&line).unwrap();
+        assert!(equation.solve_with_precedence(&part_two()).is_none());
+    }
+
+    #[test]
+    fn check_witness() {
+        let equation = Equation::<u64>::parse("14: 2 3 4").unwrap();
+        let operators = part_one();
+
+        assert_eq!(equation.witness(&operators, Evaluation::LeftToRight), None);
+        assert_eq!(
+            equation.witness(&operators, Evaluation::Precedence),
+            Some("2 + 3 * 4".to_string())
+        );
+        assert_eq!(
+            equation.witness(&operators, Evaluation::Parenthesised),
+            Some("2 + (3 * 4)".to_string())
+        );
+
+        let equation = Equation::parse("20: 2 3 4").unwrap();
+        assert_eq!(equation.witness(&operators, Evaluation::Precedence), None);
+        assert_eq!(
+            equation.witness(&operators, Evaluation::LeftToRight),
+            Some("2 + 3 * 4".to_string())
+        );
+        assert_eq!(
+            equation.witness(&operators, Evaluation::Parenthesised),
+            Some("(2 + 3) * 4".to_string())
+        );
+    }
+
+    #[test]
+    fn check_parenthesised() {
+        // No step may go below zero, in any grouping.
+        let operators = parse_operators::<u64>("add,sub").unwrap();
+        let equation = Equation::parse("4: 2 5 7").unwrap();
+        assert_eq!(equation.witness(&operators, Evaluation::LeftToRight), None);
+        assert_eq!(
+            equation.witness(&operators, Evaluation::Parenthesised),
+            None
+        );
+        let equation = Equation::parse("4: 9 5 0").unwrap();
+        assert_eq!(
+            equation.witness(&operators, Evaluation::Parenthesised),
+            Some("9 - (5 + 0)".to_string())
+        );
+
+        // Every grouping that evaluates left to right is allowed too.
+        let equations: Vec<Equation> = [
+            "190: 10 19",
+            "3267: 81 40 27",
+            "7290: 6 8 6 15",
+            "156: 15 6",
+            "292: 11 6 16 20",
+        ]
+        .iter()
+        .map(|line| Equation::parse(line).unwrap())
+        .collect();
+        for equation in &equations {
+            assert!(equation
+                .witness(&part_two(), Evaluation::Parenthesised)
+                .is_some());
+        }
+        assert_eq!(
+            Equation::<u64>::parse("83: 17 5")
+                .unwrap()
+                .witness(&part_two(), Evaluation::Parenthesised),
+            None
+        );
+
+        // A puzzle-sized line: its longest runs have far too many values to
+        // list.
+        let equation =
+            Equation::<u64>::parse("20373087843858: 62 91 675 84 53 27 47 738 903 843 25 208")
+                .unwrap();
+        let witness = equation
+            .witness(&part_two(), Evaluation::Parenthesised)
+            .unwrap();
+        assert_eq!(evaluate_parenthesised(&witness), Some(equation.target));
+    }
+
+    #[test]
+    fn check_parse_evaluation() {
+        assert_eq!(Evaluation::parse("left"), Ok(Evaluation::LeftToRight));
+        assert_eq!(Evaluation::parse("Precedence"), Ok(Evaluation::Precedence));
+        assert_eq!(Evaluation::parse("parens"), Ok(Evaluation::Parenthesised));
+        assert!(Evaluation::parse("right").is_err());
+    }
+}
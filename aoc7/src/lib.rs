@@ -0,0 +1,544 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+mod count;
+mod evaluation;
+mod number;
+mod operator;
+
+pub use count::render_solutions;
+pub use evaluation::Evaluation;
+pub use number::{BigUint, Number};
+pub use operator::{
+    parse_operator, parse_operators, part_one, part_two, Add, Concatenate, Divide, Multiply,
+    Operator, Power, Range, Subtract, Xor,
+};
+
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    let input = fs::read_to_string(&config.file_path)?;
+    if config.big {
+        report::<BigUint>(&input, &config)
+    } else {
+        report::<u64>(&input, &config)
+    }
+}
+
+fn report<N: Number>(input: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    let equations = input
+        .lines()
+        .map(Equation::<N>::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut sets = Vec::new();
+    if config.operators.is_empty() {
+        sets.push(("".to_string(), part_one()));
+        sets.push((" (with concatenation)".to_string(), part_two()));
+    }
+    for list in &config.operators {
+        let operators = parse_operators(list)?;
+        let symbols: Vec<String> = operators.iter().map(|operator| operator.symbol()).collect();
+        sets.push((format!(" (with {})", symbols.join(" ")), operators));
+    }
+
+    for (label, operators) in sets {
+        match total_calibration(&equations, &operators, config.evaluation) {
+            Some(total) => println!("Total true calibration results{}: {}", label, total),
+            None => return Err("Total does not fit in 64 bits, try --big.".into()),
+        }
+        if config.report || config.list {
+            print!("{}", render_solutions(&equations, &operators, config.list));
+        }
+        if config.witness {
+            for equation in &equations {
+                if let Some(witness) = equation.witness(&operators, config.evaluation) {
+                    println!("{} = {}", equation.target, witness);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sum of the targets of every equation that `operators` can satisfy under
+/// `evaluation`, or `None` if it cannot be represented.
+fn total_calibration<N: Number>(
+    equations: &[Equation<N>],
+    operators: &[Box<dyn Operator<N>>],
+    evaluation: Evaluation,
+) -> Option<N> {
+    equations
+        .iter()
+        .filter(|equation| equation.witness(operators, evaluation).is_some())
+        .try_fold(N::zero(), |total, equation| {
+            total.checked_add(&equation.target)
+        })
+}
+
+/// A calibration line: `target: numbers...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation<N = u64> {
+    pub target: N,
+    pub numbers: Vec<N>,
+}
+
+impl<N: Number> Equation<N> {
+    pub fn parse(line: &str) -> Result<Equation<N>, &'static str> {
+        let (target, numbers) = match line.split_once(':') {
+            Some(x) => x,
+            None => return Err("Invalid input! Could not parse row."),
+        };
+        let target = match N::parse(target.trim()) {
+            Some(x) => x,
+            None => return Err("Invalid input! Could not parse target, try --big for large ones."),
+        };
+        let numbers = match numbers
+            .split_whitespace()
+            .map(N::parse)
+            .collect::<Option<Vec<N>>>()
+        {
+            Some(x) => x,
+            None => {
+                return Err("Invalid input! Could not parse numbers, try --big for large ones.")
+            }
+        };
+        if numbers.is_empty() {
+            return Err("Invalid input! Row without numbers.");
+        }
+
+        Ok(Equation { target, numbers })
+    }
+
+    /// Operators from `operators` that, placed between the numbers and
+    /// evaluated left to right, give the target. An equation without numbers
+    /// has no solution.
+    ///
+    /// Works backwards from the target, undoing the last operator first, so
+    /// that a branch is dropped as soon as the remaining value cannot be
+    /// reached: by subtraction that would go below zero, by a division that
+    /// is not exact, or by a concatenation whose suffix does not match.
+    /// Operators that lose information, such as integer division, turn the
+    /// value sought into a range of values.
+    pub fn solve<'a>(
+        &self,
+        operators: &'a [Box<dyn Operator<N>>],
+    ) -> Option<Vec<&'a dyn Operator<N>>> {
+        let last = self.numbers.len().checked_sub(1)?;
+        let mut solution = Vec::with_capacity(last);
+        if self.solve_from(
+            last,
+            Range::single(self.target.clone()),
+            operators,
+            &mut solution,
+        ) {
+            Some(solution)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the first `i + 1` numbers can give a value in `results`; if
+    /// so, `solution` is extended with the operators used between them.
+    fn solve_from<'a>(
+        &self,
+        i: usize,
+        results: Range<N>,
+        operators: &'a [Box<dyn Operator<N>>],
+        solution: &mut Vec<&'a dyn Operator<N>>,
+    ) -> bool {
+        if i == 0 {
+            return results.contains(&self.numbers[0]);
+        }
+
+        for operator in operators {
+            for range in operator.undo(&results, &self.numbers[i]) {
+                if self.solve_from(i - 1, range, operators, solution) {
+                    solution.push(operator.as_ref());
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Evaluates the numbers left to right with `operators` between them,
+    /// or `None` if there are no numbers or some step is undefined or
+    /// overflows.
+    pub fn evaluate(&self, operators: &[&dyn Operator<N>]) -> Option<N> {
+        let (first, rest) = self.numbers.split_first()?;
+        rest.iter()
+            .zip(operators)
+            .try_fold(first.clone(), |x, (y, operator)| operator.apply(&x, y))
+    }
+
+    /// The numbers with `operators` between them, e.g. `81 + 40 * 27`.
+    pub fn expression(&self, operators: &[&dyn Operator<N>]) -> String {
+        let Some((first, rest)) = self.numbers.split_first() else {
+            return String::new();
+        };
+        let mut expression = first.to_string();
+        for (y, operator) in rest.iter().zip(operators) {
+            expression.push_str(&format!(" {} {}", operator.symbol(), y));
+        }
+        expression
+    }
+}
+
+pub struct Config {
+    pub file_path: String,
+    /// Operator lists, as given to `parse_operators`, to check instead of
+    /// the two parts of the puzzle.
+    pub operators: Vec<String>,
+    /// Whether to use arbitrary-precision numbers.
+    pub big: bool,
+    /// Whether to print the number of solutions of every equation.
+    pub report: bool,
+    /// Whether to also list those solutions.
+    pub list: bool,
+    pub evaluation: Evaluation,
+    /// Whether to print an expression for every satisfiable equation.
+    pub witness: bool,
+}
+
+impl<N: Number> fmt::Display for Equation<N> {
+    /// The equation as it appears in the input.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.target)?;
+        for number in &self.numbers {
+            write!(f, " {}", number)?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+        args.next();
+
+        let mut file_path = None;
+        let mut operators = Vec::new();
+        let mut big = false;
+        let mut report = false;
+        let mut list = false;
+        let mut evaluation = Evaluation::LeftToRight;
+        let mut witness = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--operators" => match args.next() {
+                    Some(list) => {
+                        parse_operators::<u64>(&list)?;
+                        operators.push(list);
+                    }
+                    None => return Err("Did not get a list of operators!"),
+                },
+                "--big" => big = true,
+                "--report" => report = true,
+                "--list" => list = true,
+                "--eval" => match args.next() {
+                    Some(mode) => evaluation = Evaluation::parse(&mode)?,
+                    None => return Err("Did not get an evaluation mode!"),
+                },
+                "--witness" => witness = true,
+                _ if file_path.is_none() && !arg.starts_with("--") => file_path = Some(arg),
+                _ => {
+                    return Err(
+                        "Unknown argument. Usage: aoc7 [file] [--operators add,mul,...] [--big] [--report] [--list] [--eval left|precedence|parens] [--witness]",
+                    )
+                }
+            }
+        }
+
+        if (report || list) && evaluation != Evaluation::LeftToRight {
+            return Err("Solutions can only be counted for left to right evaluation.");
+        }
+
+        Ok(Config {
+            file_path: file_path.unwrap_or("data.txt".to_string()),
+            operators,
+            big,
+            report,
+            list,
+            evaluation,
+            witness,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20";
+
+    fn equations() -> Vec<Equation> {
+        EXAMPLE
+            .lines()
+            .map(|line| Equation::parse(line).unwrap())
+            .collect()
+    }
+
+    /// The expression `solve` finds, if any.
+    fn solution(equation: &Equation, operators: &[Box<dyn Operator<u64>>]) -> Option<String> {
+        let solution = equation.solve(operators)?;
+        assert_eq!(solution.len(), equation.numbers.len() - 1);
+        assert_eq!(equation.evaluate(&solution), Some(equation.target));
+        Some(equation.expression(&solution))
+    }
+
+    /// Whether some choice of operators gives the target, trying them all.
+    fn brute_force(equation: &Equation, operators: &[Box<dyn Operator<u64>>]) -> bool {
+        let mut values = vec![equation.numbers[0]];
+        for &y in &equation.numbers[1..] {
+            values = values
+                .iter()
+                .flat_map(|&x| {
+                    operators
+                        .iter()
+                        .filter_map(move |operator| operator.apply(&x, &y))
+                })
+                .collect();
+        }
+        values.contains(&equation.target)
+    }
+
+    #[test]
+    fn check_total_calibration() {
+        let equations = equations();
+        let totals = |evaluation| {
+            (
+                total_calibration(&equations, &part_one(), evaluation),
+                total_calibration(&equations, &part_two(), evaluation),
+            )
+        };
+
+        assert_eq!(totals(Evaluation::LeftToRight), (Some(3749), Some(11387)));
+        assert_eq!(totals(Evaluation::Precedence), (Some(3457), Some(3613)));
+        assert_eq!(totals(Evaluation::Parenthesised), (Some(3749), Some(11387)));
+    }
+
+    #[test]
+    fn check_solve() {
+        let equations = equations();
+
+        for equation in &equations {
+            assert_eq!(
+                solution(equation, &part_two()).is_some(),
+                brute_force(equation, &part_two())
+            );
+        }
+        assert_eq!(
+            solution(&equations[1], &part_one()).as_deref(),
+            Some("81 * 40 + 27")
+        );
+        assert_eq!(
+            solution(&equations[4], &part_two()).as_deref(),
+            Some("6 * 8 || 6 * 15")
+        );
+        assert_eq!(solution(&equations[2], &part_two()), None);
+    }
+
+    #[test]
+    fn check_operator_sets() {
+        let all = parse_operators("add,sub,mul,div,pow,xor,cat,cat2").unwrap();
+        let mut state: u64 = 7;
+        let mut random = |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+
+        for _ in 0..300 {
+            let subset: Vec<Box<dyn Operator<u64>>> = all
+                .iter()
+                .filter(|_| random(2) == 0)
+                .map(|operator| parse_operator(&name(operator.as_ref())).unwrap())
+                .collect();
+            let equation = Equation {
+                target: random(50),
+                numbers: (0..1 + random(5)).map(|_| random(12)).collect(),
+            };
+            assert_eq!(
+                solution(&equation, &subset).is_some(),
+                brute_force(&equation, &subset),
+                "{:?} with {:?}",
+                equation,
+                subset
+                    .iter()
+                    .map(|operator| operator.symbol())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        let equation = Equation::parse("3: 20 5 1").unwrap();
+        let operators = parse_operators("div,sub").unwrap();
+        assert_eq!(
+            solution(&equation, &operators).as_deref(),
+            Some("20 / 5 - 1")
+        );
+        let equation = Equation::parse("15: 3 2 1").unwrap();
+        let operators = parse_operators("cat2,pow,xor").unwrap();
+        assert_eq!(
+            solution(&equation, &operators).as_deref(),
+            Some("3 ||2 2 xor 1")
+        );
+    }
+
+    /// The name `parse_operator` knows `operator` by.
+    fn name(operator: &dyn Operator<u64>) -> String {
+        match operator.symbol().as_str() {
+            "+" => "add".to_string(),
+            "-" => "sub".to_string(),
+            "*" => "mul".to_string(),
+            "/" => "div".to_string(),
+            "^" => "pow".to_string(),
+            "||" => "cat".to_string(),
+            symbol => symbol.replace("||", "cat"),
+        }
+    }
+
+    #[test]
+    fn check_zeros() {
+        let equation = Equation::parse("0: 3 4 0").unwrap();
+        assert!(solution(&equation, &part_one()).is_some());
+        let equation = Equation::parse("30: 3 0").unwrap();
+        assert_eq!(solution(&equation, &part_two()).as_deref(), Some("3 || 0"));
+        assert_eq!(
+            solution(&Equation::parse("5: 5").unwrap(), &[]).as_deref(),
+            Some("5")
+        );
+    }
+
+    #[test]
+    fn check_no_numbers() {
+        // The fields are public, so an equation may be built without numbers.
+        let equation = Equation::<u64> {
+            target: 0,
+            numbers: Vec::new(),
+        };
+        let operators = part_two();
+
+        assert!(equation.solve(&operators).is_none());
+        assert_eq!(equation.evaluate(&[]), None);
+        assert_eq!(equation.expression(&[]), "");
+        assert_eq!(equation.count_solutions(&operators), BigUint::zero());
+        assert!(equation.solutions(&operators).is_empty());
+        for evaluation in [
+            Evaluation::LeftToRight,
+            Evaluation::Precedence,
+            Evaluation::Parenthesised,
+        ] {
+            assert_eq!(equation.witness(&operators, evaluation), None);
+        }
+        assert_eq!(equation.evaluate_with_precedence(&[]), None);
+    }
+
+    #[test]
+    fn check_config() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        let config = Config::build(args(&["aoc7"]).into_iter()).unwrap();
+        assert_eq!(config.file_path, "data.txt");
+        assert!(config.operators.is_empty());
+
+        let config = Config::build(
+            args(&[
+                "aoc7",
+                "input.txt",
+                "--operators",
+                "add,sub",
+                "--operators",
+                "cat16",
+            ])
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(config.file_path, "input.txt");
+        assert_eq!(config.operators, ["add,sub", "cat16"]);
+        assert!(!config.big);
+        assert!(
+            Config::build(args(&["aoc7", "--big"]).into_iter())
+                .unwrap()
+                .big
+        );
+
+        assert!(Config::build(args(&["aoc7", "--operators"]).into_iter()).is_err());
+        assert!(Config::build(args(&["aoc7", "--operators", "mod"]).into_iter()).is_err());
+        assert!(Config::build(args(&["aoc7", "a.txt", "b.txt"]).into_iter()).is_err());
+    }
+
+    #[test]
+    fn check_big() {
+        let equations: Vec<Equation<BigUint>> = EXAMPLE
+            .lines()
+            .map(|line| Equation::parse(line).unwrap())
+            .collect();
+        assert_eq!(
+            total_calibration(&equations, &part_two(), Evaluation::LeftToRight),
+            Some(BigUint::from_u64(11387))
+        );
+
+        let line = "36893488147419103232: 4294967296 4294967296 2";
+        assert!(Equation::<u64>::parse(line).is_err());
+        let equation = Equation::<BigUint>::parse(line).unwrap();
+        let operators = part_one();
+        let solution = equation.solve(&operators).unwrap();
+        assert_eq!(
+            equation.expression(&solution),
+            "4294967296 * 4294967296 * 2"
+        );
+        assert_eq!(equation.evaluate(&solution), Some(equation.target.clone()));
+
+        // Wrapping around 64 bits would give false matches.
+        let line = "18446744073709551615: 1844674407370955161 5";
+        let equation = Equation::<u64>::parse(line).unwrap();
+        assert_eq!(
+            equation
+                .solve(&part_two())
+                .map(|solution| equation.expression(&solution)),
+            Some("1844674407370955161 || 5".to_string())
+        );
+        let equation = Equation::<u64>::parse("6: 18446744073709551615 10 7").unwrap();
+        assert!(equation
+            .solve(&parse_operators("cat,mul,xor").unwrap())
+            .is_none());
+
+        let totals = [
+            Equation::<u64>::parse("18446744073709551615: 18446744073709551615").unwrap(),
+            Equation::<u64>::parse("1: 1").unwrap(),
+        ];
+        assert_eq!(
+            total_calibration(&totals, &part_one(), Evaluation::LeftToRight),
+            None
+        );
+    }
+
+    #[test]
+    fn check_parse() {
+        assert_eq!(
+            Equation::<u64>::parse("190:  10 19").unwrap().to_string(),
+            "190: 10 19"
+        );
+        assert_eq!(
+            Equation::parse("190: 10 19"),
+            Ok(Equation {
+                target: 190,
+                numbers: vec![10, 19]
+            })
+        );
+        assert!(Equation::<u64>::parse("190 10 19").is_err());
+        assert!(Equation::<u64>::parse("190:").is_err());
+        assert!(Equation::<u64>::parse("x: 1").is_err());
+    }
+}
@@ -0,0 +1,427 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+
+/// Unsigned integers the solver can work with. Checked operations return
+/// `None` when the result cannot be represented.
+pub trait Number: Clone + Ord + Hash + fmt::Display + fmt::Debug {
+    fn from_u64(n: u64) -> Self;
+    fn to_u64(&self) -> Option<u64>;
+    /// The largest value, if there is one.
+    fn max_value() -> Option<Self>;
+    /// Parses a decimal number, or `None` if it is malformed or too large.
+    fn parse(digits: &str) -> Option<Self>;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_pow(&self, exp: &Self) -> Option<Self>;
+    /// Quotient and remainder; `other` must not be zero.
+    fn div_rem(&self, other: &Self) -> (Self, Self);
+
+    fn xor(&self, other: &Self) -> Self;
+    fn and(&self, other: &Self) -> Self;
+    /// `2^bits - 1`, saturating at the largest value if there is one.
+    fn mask(bits: u64) -> Self;
+    /// Number of significant bits, 0 for zero.
+    fn bits(&self) -> u64;
+    /// Number of trailing zero bits; `self` must not be zero.
+    fn trailing_zeros(&self) -> u64;
+
+    fn zero() -> Self {
+        Self::from_u64(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn div_ceil(&self, other: &Self) -> Self {
+        let (quotient, remainder) = self.div_rem(other);
+        if remainder.is_zero() {
+            quotient
+        } else {
+            quotient.checked_add(&Self::from_u64(1)).unwrap()
+        }
+    }
+}
+
+impl Number for u64 {
+    fn from_u64(n: u64) -> u64 {
+        n
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        Some(*self)
+    }
+
+    fn max_value() -> Option<u64> {
+        Some(u64::MAX)
+    }
+
+    fn parse(digits: &str) -> Option<u64> {
+        if !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    fn checked_add(&self, other: &u64) -> Option<u64> {
+        u64::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &u64) -> Option<u64> {
+        u64::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &u64) -> Option<u64> {
+        u64::checked_mul(*self, *other)
+    }
+
+    fn checked_pow(&self, exp: &u64) -> Option<u64> {
+        match u32::try_from(*exp) {
+            Ok(exp) => u64::checked_pow(*self, exp),
+            // Only 0 and 1 survive such a large exponent.
+            Err(_) => (*self <= 1).then_some(*self),
+        }
+    }
+
+    fn div_rem(&self, other: &u64) -> (u64, u64) {
+        (self / other, self % other)
+    }
+
+    fn xor(&self, other: &u64) -> u64 {
+        self ^ other
+    }
+
+    fn and(&self, other: &u64) -> u64 {
+        self & other
+    }
+
+    fn mask(bits: u64) -> u64 {
+        if bits >= 64 {
+            u64::MAX
+        } else {
+            (1 << bits) - 1
+        }
+    }
+
+    fn bits(&self) -> u64 {
+        (64 - self.leading_zeros()) as u64
+    }
+
+    fn trailing_zeros(&self) -> u64 {
+        u64::trailing_zeros(*self) as u64
+    }
+}
+
+/// Powers with more bits than this are treated as not representable.
+const MAX_BITS: u64 = 1 << 20;
+
+/// An arbitrary-precision unsigned integer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    /// Base `2^32` digits, least significant first, without leading zeros.
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_limbs(mut limbs: Vec<u32>) -> BigUint {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    fn bit(&self, idx: u64) -> bool {
+        self.limbs
+            .get((idx / 32) as usize)
+            .is_some_and(|limb| limb >> (idx % 32) & 1 == 1)
+    }
+
+    /// `self * 2 + bit`.
+    fn shift_in(&mut self, bit: bool) {
+        let mut carry = bit as u32;
+        for limb in self.limbs.iter_mut() {
+            let next = *limb >> 31;
+            *limb = *limb << 1 | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            self.limbs.push(carry);
+        }
+    }
+
+    /// Quotient and remainder of a division by a single limb.
+    fn div_rem_small(&self, divisor: u32) -> (BigUint, u32) {
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder: u64 = 0;
+        for (idx, &limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder << 32 | limb as u64;
+            quotient[idx] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        (BigUint::from_limbs(quotient), remainder as u32)
+    }
+
+    fn zip_with(&self, other: &BigUint, f: impl Fn(u32, u32) -> u32) -> BigUint {
+        let len = self.limbs.len().max(other.limbs.len());
+        let limb = |limbs: &[u32], idx: usize| limbs.get(idx).copied().unwrap_or(0);
+        BigUint::from_limbs(
+            (0..len)
+                .map(|idx| f(limb(&self.limbs, idx), limb(&other.limbs, idx)))
+                .collect(),
+        )
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, chunk) = rest.div_rem_small(1_000_000_000);
+            chunks.push(chunk);
+            rest = quotient;
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, others)) => {
+                write!(f, "{}", first)?;
+                for chunk in others.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Number for BigUint {
+    fn from_u64(n: u64) -> BigUint {
+        BigUint::from_limbs(vec![n as u32, (n >> 32) as u32])
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [low] => Some(low as u64),
+            [low, high] => Some((high as u64) << 32 | low as u64),
+            _ => None,
+        }
+    }
+
+    fn max_value() -> Option<BigUint> {
+        None
+    }
+
+    fn parse(digits: &str) -> Option<BigUint> {
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+        let ten = BigUint::from_u64(10);
+        digits.bytes().try_fold(BigUint::zero(), |value, digit| {
+            value
+                .checked_mul(&ten)?
+                .checked_add(&BigUint::from_u64((digit - b'0') as u64))
+        })
+    }
+
+    fn checked_add(&self, other: &BigUint) -> Option<BigUint> {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0;
+        for idx in 0..len {
+            let sum = self.limbs.get(idx).copied().unwrap_or(0) as u64
+                + other.limbs.get(idx).copied().unwrap_or(0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if self < other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0;
+        for (idx, &limb) in self.limbs.iter().enumerate() {
+            let subtrahend = other.limbs.get(idx).copied().unwrap_or(0) as i64 + borrow;
+            let difference = limb as i64 - subtrahend;
+            borrow = (difference < 0) as i64;
+            limbs.push((difference + (borrow << 32)) as u32);
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    fn checked_mul(&self, other: &BigUint) -> Option<BigUint> {
+        let mut limbs = vec![0_u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0_u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    fn checked_pow(&self, exp: &BigUint) -> Option<BigUint> {
+        let one = BigUint::from_u64(1);
+        if exp.is_zero() {
+            return Some(one);
+        }
+        if *self <= one {
+            return Some(self.clone());
+        }
+        if exp.bits() > 32 || (self.bits() - 1) * exp.limbs[0] as u64 > MAX_BITS {
+            return None;
+        }
+
+        let mut result = one;
+        for idx in (0..exp.bits()).rev() {
+            result = result.checked_mul(&result)?;
+            if exp.bit(idx) {
+                result = result.checked_mul(self)?;
+            }
+        }
+        Some(result)
+    }
+
+    fn div_rem(&self, other: &BigUint) -> (BigUint, BigUint) {
+        assert!(!other.is_zero(), "division by zero");
+        if let [divisor] = other.limbs[..] {
+            let (quotient, remainder) = self.div_rem_small(divisor);
+            return (quotient, BigUint::from_u64(remainder as u64));
+        }
+
+        let mut quotient = vec![0_u32; self.limbs.len()];
+        let mut remainder = BigUint::zero();
+        for idx in (0..self.bits()).rev() {
+            remainder.shift_in(self.bit(idx));
+            if remainder >= *other {
+                remainder = remainder.checked_sub(other).unwrap();
+                quotient[(idx / 32) as usize] |= 1 << (idx % 32);
+            }
+        }
+        (BigUint::from_limbs(quotient), remainder)
+    }
+
+    fn xor(&self, other: &BigUint) -> BigUint {
+        self.zip_with(other, |a, b| a ^ b)
+    }
+
+    fn and(&self, other: &BigUint) -> BigUint {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    fn mask(bits: u64) -> BigUint {
+        let mut limbs = vec![u32::MAX; (bits / 32) as usize];
+        if !bits.is_multiple_of(32) {
+            limbs.push((1 << (bits % 32)) - 1);
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(&top) => 32 * self.limbs.len() as u64 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn trailing_zeros(&self) -> u64 {
+        let idx = self.limbs.iter().position(|&limb| limb != 0).unwrap();
+        32 * idx as u64 + self.limbs[idx].trailing_zeros() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(n: u128) -> BigUint {
+        BigUint::parse(&n.to_string()).unwrap()
+    }
+
+    #[test]
+    fn check_big_arithmetic() {
+        let values: [u128; 8] = [
+            0,
+            1,
+            7,
+            u32::MAX as u128,
+            1 << 32,
+            u64::MAX as u128,
+            123_456_789_012_345_678_901,
+            (1 << 100) + 12345,
+        ];
+
+        for &a in &values {
+            assert_eq!(big(a).to_string(), a.to_string());
+            assert_eq!(big(a).bits(), (128 - a.leading_zeros()) as u64);
+            assert_eq!(big(a).to_u64(), u64::try_from(a).ok());
+            for &b in &values {
+                assert_eq!(big(a).cmp(&big(b)), a.cmp(&b));
+                assert_eq!(big(a).xor(&big(b)), big(a ^ b));
+                assert_eq!(big(a).and(&big(b)), big(a & b));
+                assert_eq!(big(a).checked_sub(&big(b)), a.checked_sub(b).map(big));
+                if let Some(sum) = a.checked_add(b) {
+                    assert_eq!(big(a).checked_add(&big(b)), Some(big(sum)));
+                }
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!(big(a).checked_mul(&big(b)), Some(big(product)));
+                }
+                if b != 0 {
+                    assert_eq!(big(a).div_rem(&big(b)), (big(a / b), big(a % b)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_big_pow() {
+        assert_eq!(big(3).checked_pow(&big(40)), Some(big(3_u128.pow(40))));
+        assert_eq!(big(2).checked_pow(&big(0)), Some(big(1)));
+        assert_eq!(big(1).checked_pow(&big(u64::MAX as u128 + 1)), Some(big(1)));
+        assert_eq!(big(2).checked_pow(&big(MAX_BITS as u128 + 1)), None);
+        assert_eq!(
+            big(10).checked_pow(&big(30)).unwrap().to_string(),
+            format!("1{}", "0".repeat(30))
+        );
+    }
+
+    #[test]
+    fn check_masks() {
+        assert_eq!(BigUint::mask(0), big(0));
+        assert_eq!(BigUint::mask(70), big((1 << 70) - 1));
+        assert_eq!(big(1 << 70).trailing_zeros(), 70);
+        assert_eq!(<u64 as Number>::mask(64), u64::MAX);
+        assert_eq!(<u64 as Number>::mask(3), 7);
+        assert_eq!(BigUint::parse("12a"), None);
+        assert_eq!(<u64 as Number>::parse("18446744073709551616"), None);
+        assert_eq!(BigUint::parse("+5"), None);
+        assert_eq!(<u64 as Number>::parse("+5"), None);
+    }
+}
@@ -0,0 +1,734 @@
+use crate::number::Number;
+
+/// A binary operator that can be placed between two numbers of a
+/// calibration equation.
+pub trait Operator<N: Number> {
+    /// How the operator is written between its operands.
+    fn symbol(&self) -> String;
+
+    /// How tightly the operator binds when evaluating with precedence;
+    /// higher binds tighter.
+    fn precedence(&self) -> u8;
+
+    /// Whether `a op b op c` means `a op (b op c)` under precedence.
+    fn right_associative(&self) -> bool {
+        false
+    }
+
+    /// Whether `apply(x, y)` is at least `x` and `y` for all `x, y >= 1`,
+    /// so that no part of an expression exceeds its value.
+    fn grows(&self) -> bool {
+        false
+    }
+
+    /// `x op y`, or `None` if that is undefined or cannot be represented.
+    fn apply(&self, x: &N, y: &N) -> Option<N>;
+
+    /// Every `x` for which `apply(x, y)` lies in `results`, as disjoint
+    /// ranges.
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>>;
+
+    /// Every `y` for which `apply(x, y)` lies in `results`, as disjoint
+    /// ranges.
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>>;
+}
+
+/// The numbers from `start` to `end` inclusive. Without an `end` the range
+/// goes up to the largest value of `N`, if it has one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Range<N> {
+    pub start: N,
+    pub end: Option<N>,
+}
+
+impl<N: Number> Range<N> {
+    pub fn single(value: N) -> Range<N> {
+        Range {
+            start: value.clone(),
+            end: Some(value),
+        }
+    }
+
+    /// The end of the range, made explicit if `N` has a largest value.
+    fn end_or_max(&self) -> Option<N> {
+        self.end.clone().or_else(N::max_value)
+    }
+
+    pub fn contains(&self, value: &N) -> bool {
+        self.start <= *value && self.end.as_ref().is_none_or(|end| value <= end)
+    }
+}
+
+pub struct Add;
+pub struct Subtract;
+pub struct Multiply;
+/// Integer division, rounding down.
+pub struct Divide;
+pub struct Power;
+pub struct Xor;
+/// Writes the digits of `y` after those of `x`, both in `base`.
+pub struct Concatenate {
+    pub base: u64,
+}
+
+/// Operators allowed in the first part of the puzzle.
+pub fn part_one<N: Number>() -> Vec<Box<dyn Operator<N>>> {
+    vec![Box::new(Add), Box::new(Multiply)]
+}
+
+/// Operators allowed in the second part of the puzzle.
+pub fn part_two<N: Number>() -> Vec<Box<dyn Operator<N>>> {
+    vec![
+        Box::new(Add),
+        Box::new(Multiply),
+        Box::new(Concatenate { base: 10 }),
+    ]
+}
+
+/// Parses a comma separated list of operator names, see `parse_operator`.
+pub fn parse_operators<N: Number>(list: &str) -> Result<Vec<Box<dyn Operator<N>>>, &'static str> {
+    list.split(',').map(parse_operator).collect()
+}
+
+/// Operators are named `add`, `sub`, `mul`, `div`, `pow`, `xor` and `cat`;
+/// `catN` concatenates in base `N`.
+pub fn parse_operator<N: Number>(name: &str) -> Result<Box<dyn Operator<N>>, &'static str> {
+    match name.trim().to_lowercase().as_str() {
+        "add" => Ok(Box::new(Add)),
+        "sub" => Ok(Box::new(Subtract)),
+        "mul" => Ok(Box::new(Multiply)),
+        "div" => Ok(Box::new(Divide)),
+        "pow" => Ok(Box::new(Power)),
+        "xor" => Ok(Box::new(Xor)),
+        "cat" => Ok(Box::new(Concatenate { base: 10 })),
+        name => match name.strip_prefix("cat").map(|base| base.parse::<u64>()) {
+            Some(Ok(base)) if base >= 2 => Ok(Box::new(Concatenate { base })),
+            Some(_) => Err("Concatenation base must be a number of at least 2."),
+            None => Err(
+                "Unknown operator. Allowed operators: ['add', 'sub', 'mul', 'div', 'pow', 'xor', 'cat', 'catN']",
+            ),
+        },
+    }
+}
+
+/// `start..=end` as a single-element list, or an empty one if `start > end`.
+fn interval<N: Number>(start: N, end: Option<N>) -> Vec<Range<N>> {
+    if end.as_ref().is_some_and(|end| start > *end) {
+        Vec::new()
+    } else {
+        vec![Range { start, end }]
+    }
+}
+
+/// Every number if `value` lies in `results`, nothing otherwise.
+fn anything_if<N: Number>(results: &Range<N>, value: &N) -> Vec<Range<N>> {
+    if results.contains(value) {
+        interval(N::zero(), None)
+    } else {
+        Vec::new()
+    }
+}
+
+/// `end - y`, or `Err` if that is below zero.
+fn end_minus<N: Number>(results: &Range<N>, y: &N) -> Result<Option<N>, ()> {
+    match &results.end_or_max() {
+        Some(end) => end.checked_sub(y).map(Some).ok_or(()),
+        None => Ok(None),
+    }
+}
+
+impl<N: Number> Operator<N> for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        x.checked_add(y)
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        match end_minus(results, y) {
+            Ok(end) => interval(results.start.checked_sub(y).unwrap_or(N::zero()), end),
+            Err(()) => Vec::new(),
+        }
+    }
+
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        self.undo(results, x)
+    }
+}
+
+impl<N: Number> Operator<N> for Subtract {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        x.checked_sub(y)
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        match results.start.checked_add(y) {
+            Some(start) => interval(
+                start,
+                results.end.as_ref().and_then(|end| end.checked_add(y)),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        match x.checked_sub(&results.start) {
+            Some(end) => interval(
+                results
+                    .end
+                    .as_ref()
+                    .and_then(|start| x.checked_sub(start))
+                    .unwrap_or(N::zero()),
+                Some(end),
+            ),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<N: Number> Operator<N> for Multiply {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        x.checked_mul(y)
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        if y.is_zero() {
+            return anything_if(results, &N::zero());
+        }
+        interval(
+            results.start.div_ceil(y),
+            results.end_or_max().map(|end| end.div_rem(y).0),
+        )
+    }
+
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        self.undo(results, x)
+    }
+}
+
+impl<N: Number> Operator<N> for Divide {
+    fn symbol(&self) -> String {
+        "/".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        (!y.is_zero()).then(|| x.div_rem(y).0)
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        if y.is_zero() {
+            return Vec::new();
+        }
+        let below = y.checked_sub(&N::from_u64(1)).unwrap();
+        match results.start.checked_mul(y) {
+            Some(start) => interval(
+                start,
+                results
+                    .end
+                    .as_ref()
+                    .and_then(|end| end.checked_mul(y)?.checked_add(&below)),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// `x / y` is at most `end` from just above `x / (end + 1)`, and at least
+    /// `start` up to `x / start`.
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        let one = N::from_u64(1);
+        let start = match results.end.as_ref().and_then(|end| end.checked_add(&one)) {
+            Some(above) => x.div_rem(&above).0.checked_add(&one).unwrap(),
+            None => one,
+        };
+        if results.start.is_zero() {
+            interval(start, None)
+        } else {
+            interval(start, Some(x.div_rem(&results.start).0))
+        }
+    }
+}
+
+/// The largest `x` with `x^exp <= value`, for `exp >= 2`.
+fn root<N: Number>(value: &N, exp: &N) -> N {
+    let one = N::from_u64(1);
+    let exp_bits = exp.to_u64().unwrap_or(u64::MAX);
+    if exp_bits >= value.bits() {
+        // Already `2^exp` is too large.
+        return value.clone().min(one);
+    }
+
+    let (mut lo, mut hi) = (N::zero(), N::mask(value.bits() / exp_bits + 1));
+    while hi > lo {
+        let half = hi.checked_sub(&lo).unwrap().div_ceil(&N::from_u64(2));
+        let mid = lo.checked_add(&half).unwrap();
+        match mid.checked_pow(exp) {
+            Some(power) if power <= *value => lo = mid,
+            _ => hi = mid.checked_sub(&one).unwrap(),
+        }
+    }
+    lo
+}
+
+impl<N: Number> Operator<N> for Power {
+    fn symbol(&self) -> String {
+        "^".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        4
+    }
+
+    fn right_associative(&self) -> bool {
+        true
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        x.checked_pow(y)
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        let one = N::from_u64(1);
+        if y.is_zero() {
+            return anything_if(results, &one);
+        }
+        if *y == one {
+            return vec![results.clone()];
+        }
+
+        let start = match results.start.checked_sub(&one) {
+            Some(below) => root(&below, y).checked_add(&one).unwrap(),
+            None => N::zero(),
+        };
+        interval(start, results.end_or_max().map(|end| root(&end, y)))
+    }
+
+    /// Powers of `x >= 2` grow with `y`, so the exponents that fit form a
+    /// single range.
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        let one = N::from_u64(1);
+        if x.is_zero() {
+            // `0^0` is 1, every other power of zero is 0.
+            let mut ranges = Vec::new();
+            if results.contains(&one) {
+                ranges.push(Range::single(N::zero()));
+            }
+            if results.contains(&N::zero()) {
+                ranges.push(Range {
+                    start: one,
+                    end: None,
+                });
+            }
+            return ranges;
+        }
+        if *x == one {
+            return anything_if(results, &one);
+        }
+
+        let end = results.end_or_max();
+        let mut start = None;
+        let mut y = N::zero();
+        loop {
+            match x.checked_pow(&y) {
+                Some(power) if end.as_ref().is_none_or(|end| power <= *end) => {
+                    if start.is_none() && power >= results.start {
+                        if end.is_none() {
+                            return interval(y, None);
+                        }
+                        start = Some(y.clone());
+                    }
+                }
+                _ => break,
+            }
+            y = y.checked_add(&one).unwrap();
+        }
+        match start {
+            Some(start) => interval(start, y.checked_sub(&one)),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<N: Number> Operator<N> for Xor {
+    fn symbol(&self) -> String {
+        "xor".to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        0
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        Some(x.xor(y))
+    }
+
+    /// Splits `results` into aligned blocks of a power of two; each maps to
+    /// another such block.
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        let one = N::from_u64(1);
+        let mut ranges = Vec::new();
+        let mut start = results.start.clone();
+
+        loop {
+            let mut bits = if start.is_zero() {
+                u64::MAX
+            } else {
+                start.trailing_zeros()
+            };
+            match &results.end {
+                Some(end) => {
+                    let span = match end.checked_sub(&start) {
+                        Some(span) => span,
+                        None => break,
+                    };
+                    let fits = match span.checked_add(&one) {
+                        Some(len) => len.bits() - 1,
+                        None => span.bits(),
+                    };
+                    bits = bits.min(fits);
+                }
+                None if bits >= y.bits() => {
+                    // Past a multiple of a power of two above `y`, xor only
+                    // moves numbers around within such blocks.
+                    ranges.push(Range { start, end: None });
+                    break;
+                }
+                None => {}
+            }
+
+            let mask = N::mask(bits);
+            let moved = start.xor(y);
+            let block = moved.checked_sub(&moved.and(&mask)).unwrap();
+            ranges.push(Range {
+                start: block.clone(),
+                end: block.checked_add(&mask),
+            });
+            match start
+                .checked_add(&mask)
+                .and_then(|last| last.checked_add(&one))
+            {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+        ranges
+    }
+
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        self.undo(results, x)
+    }
+}
+
+impl Concatenate {
+    /// The power of `base` that `x` is shifted by, or `None` if it cannot
+    /// be represented.
+    fn shift<N: Number>(&self, y: &N) -> Option<N> {
+        let base = N::from_u64(self.base);
+        let mut shift = base.clone();
+        let mut rest = y.div_rem(&base).0;
+        while !rest.is_zero() {
+            shift = shift.checked_mul(&base)?;
+            rest = rest.div_rem(&base).0;
+        }
+        Some(shift)
+    }
+}
+
+impl<N: Number> Operator<N> for Concatenate {
+    /// `||` in base 10, `||N` in base `N` otherwise.
+    fn symbol(&self) -> String {
+        match self.base {
+            10 => "||".to_string(),
+            base => format!("||{}", base),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+
+    fn grows(&self) -> bool {
+        true
+    }
+
+    fn apply(&self, x: &N, y: &N) -> Option<N> {
+        match self.shift(y) {
+            Some(shift) => x.checked_mul(&shift)?.checked_add(y),
+            None => x.is_zero().then(|| y.clone()),
+        }
+    }
+
+    fn undo(&self, results: &Range<N>, y: &N) -> Vec<Range<N>> {
+        let end = match end_minus(results, y) {
+            Ok(end) => end,
+            Err(()) => return Vec::new(),
+        };
+        match self.shift(y) {
+            Some(shift) => interval(
+                results
+                    .start
+                    .checked_sub(y)
+                    .unwrap_or(N::zero())
+                    .div_ceil(&shift),
+                end.map(|end| end.div_rem(&shift).0),
+            ),
+            None if results.start <= *y => vec![Range::single(N::zero())],
+            None => Vec::new(),
+        }
+    }
+
+    /// Numbers with the same count of digits are shifted alike, so each
+    /// count of digits gives at most one range.
+    fn undo_right(&self, results: &Range<N>, x: &N) -> Vec<Range<N>> {
+        if x.is_zero() {
+            return vec![results.clone()];
+        }
+
+        let base = N::from_u64(self.base);
+        let end = results.end_or_max();
+        let mut ranges = Vec::new();
+        // The numbers from `low` up to `shift - 1` all shift `x` by `shift`.
+        let mut low = N::zero();
+        let mut shift = base.clone();
+        while let Some(shifted) = x.checked_mul(&shift) {
+            match &end {
+                Some(end) if shifted > *end => break,
+                Some(end) => {
+                    let start = results.start.checked_sub(&shifted).unwrap_or(N::zero());
+                    let last = shift.checked_sub(&N::from_u64(1)).unwrap();
+                    ranges.extend(interval(
+                        start.max(low),
+                        Some(end.checked_sub(&shifted).unwrap().min(last)),
+                    ));
+                }
+                // Without an end, every longer number fits once `shifted`
+                // reaches the start.
+                None if shifted >= results.start => {
+                    ranges.push(Range {
+                        start: low,
+                        end: None,
+                    });
+                    break;
+                }
+                None => {
+                    let start = results.start.checked_sub(&shifted).unwrap();
+                    let last = shift.checked_sub(&N::from_u64(1)).unwrap();
+                    ranges.extend(interval(start.max(low), Some(last)));
+                }
+            }
+            low = shift.clone();
+            shift = match shift.checked_mul(&base) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::BigUint;
+
+    const LIMIT: u64 = 300;
+
+    /// The ranges of results the undo checks try.
+    fn result_ranges<N: Number>() -> Vec<Range<N>> {
+        let mut ranges = Vec::new();
+        for start in [0, 1, 2, 3, 5, 8, 13, 21, 34, 55] {
+            for end in [
+                Some(start),
+                Some(start + 1),
+                Some(start + 7),
+                Some(start + 64),
+                None,
+            ] {
+                ranges.push(Range {
+                    start: N::from_u64(start),
+                    end: end.map(N::from_u64),
+                });
+            }
+        }
+        ranges
+    }
+
+    /// Compares `undo` with trying every `x` below `LIMIT`.
+    fn check_undo<N: Number>(operator: &dyn Operator<N>, ys: &[u64]) {
+        for &y in ys {
+            let y = N::from_u64(y);
+            for results in result_ranges::<N>() {
+                let ranges = operator.undo(&results, &y);
+                for x in (0..LIMIT).map(N::from_u64) {
+                    let expected = operator
+                        .apply(&x, &y)
+                        .is_some_and(|result| results.contains(&result));
+                    let found = ranges.iter().filter(|range| range.contains(&x)).count();
+                    assert_eq!(
+                        found,
+                        expected as usize,
+                        "{} {} {} in {:?}",
+                        x,
+                        operator.symbol(),
+                        y,
+                        results
+                    );
+                }
+            }
+        }
+    }
+
+    /// Compares `undo_right` with trying every `y` below `LIMIT`.
+    fn check_undo_right<N: Number>(operator: &dyn Operator<N>, xs: &[u64]) {
+        for &x in xs {
+            let x = N::from_u64(x);
+            for results in result_ranges::<N>() {
+                let ranges = operator.undo_right(&results, &x);
+                for y in (0..LIMIT).map(N::from_u64) {
+                    let expected = operator
+                        .apply(&x, &y)
+                        .is_some_and(|result| results.contains(&result));
+                    let found = ranges.iter().filter(|range| range.contains(&y)).count();
+                    assert_eq!(
+                        found,
+                        expected as usize,
+                        "{} {} {} in {:?}",
+                        x,
+                        operator.symbol(),
+                        y,
+                        results
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_all_operators<N: Number>() {
+        let ys = [0, 1, 2, 3, 7, 10, 16, 99, 100, 255];
+        check_undo::<N>(&Add, &ys);
+        check_undo::<N>(&Subtract, &ys);
+        check_undo::<N>(&Multiply, &ys);
+        check_undo::<N>(&Divide, &ys);
+        check_undo::<N>(&Power, &[0, 1, 2, 3, 5, 64]);
+        check_undo::<N>(&Xor, &ys);
+        for base in [2, 3, 10, 16] {
+            check_undo::<N>(&Concatenate { base }, &ys);
+        }
+
+        let xs = [0, 1, 2, 3, 7, 10, 16, 99, 100, 255, 1000];
+        check_undo_right::<N>(&Add, &xs);
+        check_undo_right::<N>(&Subtract, &xs);
+        check_undo_right::<N>(&Multiply, &xs);
+        check_undo_right::<N>(&Divide, &xs);
+        check_undo_right::<N>(&Power, &xs);
+        check_undo_right::<N>(&Xor, &xs);
+        for base in [2, 3, 10, 16] {
+            check_undo_right::<N>(&Concatenate { base }, &xs);
+        }
+    }
+
+    #[test]
+    fn check_undo_against_apply() {
+        check_all_operators::<u64>();
+        // Too large to compute as a `BigUint`.
+        check_undo::<u64>(&Power, &[u32::MAX as u64 + 1]);
+        check_all_operators::<BigUint>();
+    }
+
+    #[test]
+    fn check_extremes() {
+        let max = Range::single(u64::MAX);
+        let everything = Range {
+            start: 0,
+            end: Some(u64::MAX),
+        };
+
+        assert_eq!(Add.undo(&max, &u64::MAX), vec![Range::single(0)]);
+        assert_eq!(Operator::<u64>::apply(&Multiply, &u64::MAX, &2), None);
+        assert_eq!(Operator::<u64>::apply(&Power, &2, &64), None);
+        assert_eq!(Power.undo(&max, &2), Vec::new());
+        assert_eq!(Xor.undo(&everything, &5), vec![everything.clone()]);
+
+        let concatenate = Concatenate { base: 10 };
+        assert_eq!(concatenate.apply(&1, &u64::MAX), None);
+        assert_eq!(concatenate.apply(&0, &u64::MAX), Some(u64::MAX));
+        assert_eq!(concatenate.undo(&max, &u64::MAX), vec![Range::single(0)]);
+        assert_eq!(concatenate.undo_right(&max, &0), vec![max.clone()]);
+        assert_eq!(concatenate.undo_right(&everything, &u64::MAX), Vec::new());
+        assert_eq!(
+            Power.undo_right(&everything, &2),
+            vec![Range {
+                start: 0,
+                end: Some(63)
+            }]
+        );
+
+        let big = |digits: &str| BigUint::parse(digits).unwrap();
+        assert_eq!(
+            concatenate.apply(&big("1"), &big("18446744073709551615")),
+            Some(big("118446744073709551615"))
+        );
+        assert_eq!(
+            Power.undo(
+                &Range::single(big("1267650600228229401496703205376")),
+                &big("2")
+            ),
+            vec![Range::single(big("1125899906842624"))]
+        );
+    }
+
+    #[test]
+    fn check_concatenate() {
+        assert_eq!(Concatenate { base: 10 }.apply(&12, &345), Some(12345));
+        assert_eq!(Concatenate { base: 10 }.apply(&12, &0), Some(120));
+        assert_eq!(Concatenate { base: 2 }.apply(&0b101, &0b11), Some(0b10111));
+        assert_eq!(Operator::<u64>::symbol(&Concatenate { base: 16 }), "||16");
+    }
+
+    #[test]
+    fn check_parse_operator() {
+        let symbols: Vec<String> = parse_operators::<u64>("add,sub,mul,div,pow,xor,cat,cat2")
+            .unwrap()
+            .iter()
+            .map(|operator| operator.symbol())
+            .collect();
+        assert_eq!(symbols, ["+", "-", "*", "/", "^", "xor", "||", "||2"]);
+
+        assert!(parse_operator::<u64>("cat1").is_err());
+        assert!(parse_operator::<u64>("catx").is_err());
+        assert!(parse_operator::<u64>("mod").is_err());
+    }
+}
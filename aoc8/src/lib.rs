@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::error::Error;
 
 pub struct Grid {
@@ -25,32 +25,103 @@ impl Grid {
 
         Ok(Self { m, n, values })
     }
-    
+
     pub fn total_antinodes(&self) -> (usize, usize) {
-        let mapping = self.get_char_mapping();
-        let mut antinodes: HashSet<i32> = HashSet::new();
-        let mut antinodes_all: HashSet<i32> = HashSet::new();
+        self.total_antinodes_with(&Resonance::default())
+    }
+
+    /// Like `total_antinodes`, placing antinodes by `resonance`.
+    pub fn total_antinodes_with(&self, resonance: &Resonance) -> (usize, usize) {
+        let frequencies = self.frequencies(resonance);
+        let antinodes: HashSet<&Position> = frequencies
+            .values()
+            .flat_map(|frequency| &frequency.antinodes)
+            .collect();
+        let antinodes_all: HashSet<&Position> = frequencies
+            .values()
+            .flat_map(|frequency| &frequency.harmonic_antinodes)
+            .collect();
 
-        for (_, positions) in mapping {
-            self.add_antinodes_from_antenna(positions, &mut antinodes, &mut antinodes_all);
-        }
         (antinodes.len(), antinodes_all.len())
     }
 
+    /// Antennas and antinodes of every frequency, keyed by frequency.
+    pub fn frequencies(&self, resonance: &Resonance) -> BTreeMap<char, Frequency> {
+        self.get_char_mapping()
+            .into_iter()
+            .map(|(frequency, indices)| {
+                let mut antinodes = BTreeSet::new();
+                let mut harmonic_antinodes = BTreeSet::new();
+                for (idx1, idx2) in pairs(&indices) {
+                    for &ratio in &resonance.ratios {
+                        let (node1, node2) = self.antinodes_from_pair(idx1, idx2, ratio);
+                        antinodes.extend(node1);
+                        antinodes.extend(node2);
+                    }
+                    harmonic_antinodes.extend(self.all_antinodes_from_pair(idx1, idx2, resonance));
+                }
+
+                let antennas = indices
+                    .iter()
+                    .map(|&idx| self.index_to_position(idx))
+                    .collect();
+                let antinodes = antinodes.into_iter().collect();
+                let harmonic_antinodes = harmonic_antinodes.into_iter().collect();
+                (
+                    frequency,
+                    Frequency {
+                        antennas,
+                        antinodes,
+                        harmonic_antinodes,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Every antenna pair that places an antinode at `position`, once per
+    /// rule under which it does.
+    pub fn antinode_sources(&self, position: Position, resonance: &Resonance) -> Vec<Source> {
+        let mut sources = Vec::new();
+        for (frequency, indices) in self.get_char_mapping() {
+            for (idx1, idx2) in pairs(&indices) {
+                let antennas = (self.index_to_position(idx1), self.index_to_position(idx2));
+                let mut push = |rule| {
+                    sources.push(Source {
+                        frequency,
+                        antennas,
+                        rule,
+                    })
+                };
+
+                for &ratio in &resonance.ratios {
+                    let (node1, node2) = self.antinodes_from_pair(idx1, idx2, ratio);
+                    if node1 == Some(position) || node2 == Some(position) {
+                        push(Rule::Ratio(ratio));
+                    }
+                }
+                if self
+                    .all_antinodes_from_pair(idx1, idx2, resonance)
+                    .contains(&position)
+                {
+                    push(Rule::Harmonic);
+                }
+            }
+        }
+
+        sources
+    }
+
     fn check_position(&self, position: &Position) -> bool {
-        (position.0 < self.n) && (position.0 >= 0) && (position.1 < self.m) && (position.1 >= 0)
+        (position.0 < self.m) && (position.0 >= 0) && (position.1 < self.n) && (position.1 >= 0)
     }
 
     fn index_to_position(&self, idx: i32) -> Position {
         Position(idx / self.n, idx % self.n)
     }
 
-    fn position_to_index(&self, position: &Position) -> i32 {
-        self.n * position.0 + position.1
-    }
-
-    fn get_char_mapping(&self) -> HashMap<char, Vec<i32>> {
-        let mut mapping: HashMap<char, Vec<i32>> = HashMap::new();
+    fn get_char_mapping(&self) -> BTreeMap<char, Vec<i32>> {
+        let mut mapping: BTreeMap<char, Vec<i32>> = BTreeMap::new();
         for (idx, entry) in self.values.chars().enumerate() {
             if entry != '.' {
                 let map_entry = mapping.entry(entry).or_default();
@@ -61,38 +132,32 @@ impl Grid {
         mapping
     }
 
-    fn add_antinodes_from_antenna(
+    /// Harmonics of a pair, from the line between the antennas (order 0)
+    /// outwards by up to `resonance.max_order` steps on either side.
+    fn all_antinodes_from_pair(
         &self,
-        positions: Vec<i32>,
-        antinodes: &mut HashSet<i32>,
-        antinodes_all: &mut HashSet<i32>,
-    ) {
-        for (i, idx1) in positions.iter().enumerate() {
-            for idx2 in positions[i + 1..].iter() {
-                let (node1, node2) = self.antinodes_from_pair(*idx1, *idx2);
-                let nodes_all = self.all_antinodes_from_pair(*idx1, *idx2);
-
-                let mut insert = |node| match node {
-                    Some(x) => antinodes.insert(self.position_to_index(&x)),
-                    None => false,
-                };
-
-                insert(node1);
-                insert(node2);
-
-                for entry in nodes_all {
-                    antinodes_all.insert(self.position_to_index(&entry));
-                }
-            }
-        }
-    }
-
-    fn all_antinodes_from_pair(&self, idx1: i32, idx2: i32) -> Vec<Position> {
+        idx1: i32,
+        idx2: i32,
+        resonance: &Resonance,
+    ) -> Vec<Position> {
         let point1 = self.index_to_position(idx1);
         let point2 = self.index_to_position(idx2);
 
-        let dx = point2.0 - point1.0;
-        let dy = point2.1 - point1.1;
+        let mut dx = point2.0 - point1.0;
+        let mut dy = point2.1 - point1.1;
+        let mut steps = 1;
+        if resonance.spacing == Spacing::Lattice {
+            steps = gcd(dx.abs(), dy.abs());
+            dx /= steps;
+            dy /= steps;
+        }
+
+        let within_order = |order: i32| {
+            order <= 0
+                || resonance
+                    .max_order
+                    .is_none_or(|max_order| i64::from(order) <= i64::from(max_order))
+        };
 
         let mut i = 0;
 
@@ -100,7 +165,7 @@ impl Grid {
 
         loop {
             let forward_position = Position(point1.0 + i * dx, point1.1 + i * dy);
-            if self.check_position(&forward_position) {
+            if self.check_position(&forward_position) && within_order(i - steps) {
                 positions.push(forward_position);
                 i += 1;
             } else {
@@ -112,7 +177,7 @@ impl Grid {
 
         loop {
             let backward_position = Position(point1.0 - i * dx, point1.1 - i * dy);
-            if self.check_position(&backward_position) {
+            if self.check_position(&backward_position) && within_order(i) {
                 positions.push(backward_position);
                 i += 1;
             } else {
@@ -123,17 +188,32 @@ impl Grid {
         positions
     }
 
-    fn antinodes_from_pair(&self, idx1: i32, idx2: i32) -> (Option<Position>, Option<Position>) {
+    /// The antinodes beyond either antenna of a pair whose distances to the
+    /// two antennas are in the ratio `far:near`.
+    fn antinodes_from_pair(
+        &self,
+        idx1: i32,
+        idx2: i32,
+        ratio: Ratio,
+    ) -> (Option<Position>, Option<Position>) {
         let point1 = self.index_to_position(idx1);
         let point2 = self.index_to_position(idx2);
 
-        let dx = point2.0 - point1.0;
-        let dy = point2.1 - point1.1;
-
-        let node1 = Position(point1.0 + 2 * dx, point1.1 + 2 * dy);
-        let node2 = Position(point1.0 - dx, point1.1 - dy);
+        let dx = i64::from(point2.0 - point1.0);
+        let dy = i64::from(point2.1 - point1.1);
 
-        let check_node = |node| {
+        // `point1 + t * (dx, dy)` with `t = far / (far - near)` beyond point2
+        // and `t = -near / (far - near)` beyond point1. Worked out in `i64`,
+        // which holds every product of an `i32` ratio and a grid distance.
+        let difference = i64::from(ratio.far) - i64::from(ratio.near);
+        let check_node = |t: i64| {
+            if (t * dx) % difference != 0 || (t * dy) % difference != 0 {
+                return None;
+            }
+            let node = Position(
+                i32::try_from(i64::from(point1.0) + t * dx / difference).ok()?,
+                i32::try_from(i64::from(point1.1) + t * dy / difference).ok()?,
+            );
             if self.check_position(&node) {
                 Some(node)
             } else {
@@ -141,8 +221,369 @@ impl Grid {
             }
         };
 
-        (check_node(node1), check_node(node2))
+        (
+            check_node(i64::from(ratio.far)),
+            check_node(-i64::from(ratio.near)),
+        )
+    }
+}
+
+/// A cell of the grid as `(row, column)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position(pub i32, pub i32);
+
+/// Antennas of a single frequency and the antinodes they produce, each
+/// sorted by position.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Frequency {
+    pub antennas: Vec<Position>,
+    /// Antinodes in line with a pair at one of the distance ratios.
+    pub antinodes: Vec<Position>,
+    /// Antinodes anywhere in line with a pair, taking resonant harmonics
+    /// into account.
+    pub harmonic_antinodes: Vec<Position>,
+}
+
+/// Step between the resonant-harmonic antinodes of a pair.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Spacing {
+    /// Whole multiples of the distance between the two antennas, as in the
+    /// puzzle.
+    #[default]
+    Antennas,
+    /// Every grid point on the line through the two antennas, stepping by
+    /// the distance divided by the gcd of its components.
+    Lattice,
+}
+
+/// Ratio of the distances from an antinode to the farther and the nearer
+/// antenna of a pair, such as 2:1 in the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    far: i32,
+    near: i32,
+}
+
+impl Ratio {
+    pub fn new(far: i32, near: i32) -> Result<Ratio, &'static str> {
+        if near <= 0 || far <= near {
+            return Err("Invalid ratio! Expected far > near > 0.");
+        }
+        Ok(Ratio { far, near })
+    }
+
+    /// Parse a ratio written as `far:near`, such as `2:1`.
+    pub fn parse(ratio: &str) -> Result<Ratio, &'static str> {
+        let (far, near) = ratio
+            .split_once(':')
+            .ok_or("Invalid ratio! Expected far:near.")?;
+        let far = far.trim().parse().map_err(|_| "Invalid ratio distance!")?;
+        let near = near.trim().parse().map_err(|_| "Invalid ratio distance!")?;
+        Ratio::new(far, near)
+    }
+
+    pub fn far(&self) -> i32 {
+        self.far
+    }
+
+    pub fn near(&self) -> i32 {
+        self.near
     }
 }
 
-struct Position(i32, i32);
+/// How pairs of antennas place antinodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resonance {
+    /// Distance ratios of the antinodes beyond either antenna of a pair.
+    pub ratios: Vec<Ratio>,
+    /// Step between resonant harmonics.
+    pub spacing: Spacing,
+    /// Greatest number of steps a harmonic lies beyond the nearer antenna,
+    /// or `None` to continue to the edge of the grid. A step is the distance
+    /// between the antennas under `Spacing::Antennas`, but one lattice step
+    /// under `Spacing::Lattice`.
+    pub max_order: Option<u32>,
+}
+
+impl Default for Resonance {
+    /// The puzzle's rules: a 2:1 ratio and harmonics up to the edge.
+    fn default() -> Self {
+        Resonance {
+            ratios: vec![Ratio::new(2, 1).unwrap()],
+            spacing: Spacing::default(),
+            max_order: None,
+        }
+    }
+}
+
+/// Rule under which a pair of antennas places an antinode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Ratio(Ratio),
+    Harmonic,
+}
+
+/// An antenna pair responsible for an antinode.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Source {
+    pub frequency: char,
+    pub antennas: (Position, Position),
+    pub rule: Rule,
+}
+
+/// Every unordered pair of antennas in `indices`.
+fn pairs(indices: &[i32]) -> impl Iterator<Item = (i32, i32)> + '_ {
+    indices
+        .iter()
+        .enumerate()
+        .flat_map(move |(i, &idx1)| indices[i + 1..].iter().map(move |&idx2| (idx1, idx2)))
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+............
+........0...
+.....0......
+.......0....
+....0.......
+......A.....
+............
+............
+........A...
+.........A..
+............
+............
+";
+
+    const SINGLE_PAIR: &str = "\
+..........
+..........
+..........
+....a.....
+..........
+.....a....
+..........
+..........
+..........
+..........
+";
+
+    fn spaced(spacing: Spacing) -> Resonance {
+        Resonance {
+            spacing,
+            ..Resonance::default()
+        }
+    }
+
+    #[test]
+    fn check_total_antinodes() {
+        let grid = Grid::build(EXAMPLE).unwrap();
+        assert_eq!(grid.total_antinodes(), (14, 34));
+
+        // Rows and columns must not be mixed up on a grid that is not square.
+        let grid = Grid::build("a.a..\n.....\n").unwrap();
+        assert_eq!(grid.total_antinodes(), (1, 3));
+    }
+
+    #[test]
+    fn check_frequencies() {
+        let grid = Grid::build(SINGLE_PAIR).unwrap();
+        let frequencies = grid.frequencies(&Resonance::default());
+        assert_eq!(frequencies.len(), 1);
+        assert_eq!(
+            frequencies[&'a'],
+            Frequency {
+                antennas: vec![Position(3, 4), Position(5, 5)],
+                antinodes: vec![Position(1, 3), Position(7, 6)],
+                harmonic_antinodes: vec![
+                    Position(1, 3),
+                    Position(3, 4),
+                    Position(5, 5),
+                    Position(7, 6),
+                    Position(9, 7),
+                ],
+            }
+        );
+
+        let grid = Grid::build(EXAMPLE).unwrap();
+        let frequencies = grid.frequencies(&Resonance::default());
+        assert_eq!(frequencies.keys().collect::<Vec<_>>(), [&'0', &'A']);
+        assert_eq!(
+            frequencies[&'A'].antennas,
+            [Position(5, 6), Position(8, 8), Position(9, 9)]
+        );
+    }
+
+    #[test]
+    fn check_antinode_sources() {
+        let grid = Grid::build(SINGLE_PAIR).unwrap();
+        let antennas = (Position(3, 4), Position(5, 5));
+        let source = |rule| Source {
+            frequency: 'a',
+            antennas,
+            rule,
+        };
+
+        assert_eq!(
+            grid.antinode_sources(Position(1, 3), &Resonance::default()),
+            [
+                source(Rule::Ratio(Ratio::new(2, 1).unwrap())),
+                source(Rule::Harmonic)
+            ]
+        );
+        assert_eq!(
+            grid.antinode_sources(Position(9, 7), &Resonance::default()),
+            [source(Rule::Harmonic)]
+        );
+        assert_eq!(
+            grid.antinode_sources(Position(0, 0), &Resonance::default()),
+            []
+        );
+    }
+
+    #[test]
+    fn check_spacing() {
+        let grid = Grid::build("a....\n.....\n..a..\n.....\n.....\n").unwrap();
+        assert_eq!(
+            grid.total_antinodes_with(&spaced(Spacing::Antennas)),
+            (1, 3)
+        );
+        assert_eq!(grid.total_antinodes_with(&spaced(Spacing::Lattice)), (1, 5));
+        assert_eq!(
+            grid.frequencies(&spaced(Spacing::Lattice))[&'a'].harmonic_antinodes,
+            (0..5).map(|i| Position(i, i)).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            grid.antinode_sources(Position(1, 1), &spaced(Spacing::Antennas)),
+            []
+        );
+        assert_eq!(
+            grid.antinode_sources(Position(1, 1), &spaced(Spacing::Lattice)),
+            [Source {
+                frequency: 'a',
+                antennas: (Position(0, 0), Position(2, 2)),
+                rule: Rule::Harmonic,
+            }]
+        );
+
+        // No pair in the example shares a common factor, so both agree.
+        let grid = Grid::build(EXAMPLE).unwrap();
+        assert_eq!(
+            grid.total_antinodes_with(&spaced(Spacing::Lattice)),
+            (14, 34)
+        );
+    }
+
+    #[test]
+    fn check_ratios() {
+        assert_eq!(Ratio::parse("3:2"), Ratio::new(3, 2));
+        assert_eq!(
+            Ratio::new(3, 2).map(|ratio| (ratio.far(), ratio.near())),
+            Ok((3, 2))
+        );
+        assert!(Ratio::parse("1:2").is_err());
+        assert!(Ratio::parse("2:2").is_err());
+        assert!(Ratio::parse("2:0").is_err());
+        assert!(Ratio::parse("2").is_err());
+
+        let ratios = |ratios: &str| Resonance {
+            ratios: ratios
+                .split(',')
+                .map(|r| Ratio::parse(r).unwrap())
+                .collect(),
+            ..Resonance::default()
+        };
+
+        let grid = Grid::build(SINGLE_PAIR).unwrap();
+        let antinodes = |resonance| grid.frequencies(&resonance)[&'a'].antinodes.clone();
+        assert_eq!(antinodes(ratios("3:2")), [Position(9, 7)]);
+        // Neither point at a 3:1 ratio lies on the grid lattice.
+        assert_eq!(antinodes(ratios("3:1")), []);
+        // Extreme ratios land far off the grid instead of overflowing.
+        assert_eq!(antinodes(ratios("2147483647:2147483646")), []);
+
+        let grid = Grid::build("a....\n.....\n..a..\n.....\n.....\n").unwrap();
+        let antinodes = |resonance| grid.frequencies(&resonance)[&'a'].antinodes.clone();
+        assert_eq!(antinodes(ratios("3:1")), [Position(3, 3)]);
+        assert_eq!(
+            antinodes(ratios("2:1,3:1")),
+            [Position(3, 3), Position(4, 4)]
+        );
+        assert_eq!(
+            grid.antinode_sources(Position(3, 3), &ratios("2:1,3:1")),
+            [Source {
+                frequency: 'a',
+                antennas: (Position(0, 0), Position(2, 2)),
+                rule: Rule::Ratio(Ratio::new(3, 1).unwrap()),
+            }]
+        );
+    }
+
+    #[test]
+    fn check_max_order() {
+        let limited = |spacing, max_order| Resonance {
+            max_order: Some(max_order),
+            ..spaced(spacing)
+        };
+
+        let grid = Grid::build(SINGLE_PAIR).unwrap();
+        let harmonics = |resonance| {
+            grid.frequencies(&resonance)[&'a']
+                .harmonic_antinodes
+                .clone()
+        };
+        assert_eq!(
+            harmonics(limited(Spacing::Antennas, 0)),
+            [Position(3, 4), Position(5, 5)]
+        );
+        assert_eq!(
+            harmonics(limited(Spacing::Antennas, 1)),
+            [
+                Position(1, 3),
+                Position(3, 4),
+                Position(5, 5),
+                Position(7, 6)
+            ]
+        );
+        assert_eq!(
+            harmonics(limited(Spacing::Antennas, 2)),
+            harmonics(Resonance::default())
+        );
+
+        let grid = Grid::build("a....\n.....\n..a..\n.....\n.....\n").unwrap();
+        assert_eq!(
+            grid.total_antinodes_with(&limited(Spacing::Lattice, 0)),
+            (1, 3)
+        );
+        assert_eq!(
+            grid.total_antinodes_with(&limited(Spacing::Lattice, 1)),
+            (1, 4)
+        );
+
+        // One step beyond each antenna is exactly where the 2:1 antinodes are.
+        let grid = Grid::build(EXAMPLE).unwrap();
+        for (_, frequency) in grid.frequencies(&limited(Spacing::Antennas, 1)) {
+            let mut expected: Vec<Position> = frequency
+                .antennas
+                .iter()
+                .chain(&frequency.antinodes)
+                .copied()
+                .collect();
+            expected.sort();
+            expected.dedup();
+            assert_eq!(frequency.harmonic_antinodes, expected);
+        }
+    }
+}